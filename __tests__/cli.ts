@@ -1,3 +1,4 @@
+import { readFileSync, rmSync } from "fs"
 import { invokeCli } from "../vitest.utils"
 
 describe(`cli`, () => {
@@ -19,4 +20,56 @@ describe(`cli`, () => {
 		expect(stdout).toMatchSnapshot()
 		expect(exitCode).toBe(1)
 	})
+	it(`should create the list from recorded responses`, () => {
+		const { exitCode, stdout } = invokeCli([
+			`v1.0.0`,
+			`v1.1.0`,
+			`acme`,
+			`widgets`,
+			`--replay`,
+			`__tests__/fixtures/replay`,
+		])
+		const filepath = stdout.replace(`Successfully created `, ``).trim()
+
+		expect(exitCode).toBe(0)
+		expect(readFileSync(filepath, `utf-8`)).toMatchSnapshot()
+
+		rmSync(filepath)
+	})
+	it(`should create the list from recorded responses in a date range`, () => {
+		const { exitCode, stdout } = invokeCli([
+			`--since`,
+			`2024-01-01`,
+			`--until`,
+			`2024-02-01`,
+			`acme`,
+			`widgets`,
+			`--replay`,
+			`__tests__/fixtures/replay`,
+		])
+		const filepath = stdout.replace(`Successfully created `, ``).trim()
+
+		expect(exitCode).toBe(0)
+		expect(readFileSync(filepath, `utf-8`)).toMatchSnapshot()
+
+		rmSync(filepath)
+	})
+	it(`should create the list from recorded GitLab responses`, () => {
+		const { exitCode, stdout } = invokeCli([
+			`v1.0.0`,
+			`v1.1.0`,
+			`acme`,
+			`widgets`,
+			`--forge`,
+			`gitlab`,
+			`--replay`,
+			`__tests__/fixtures/gitlab`,
+		])
+		const filepath = stdout.replace(`Successfully created `, ``).trim()
+
+		expect(exitCode).toBe(0)
+		expect(readFileSync(filepath, `utf-8`)).toMatchSnapshot()
+
+		rmSync(filepath)
+	})
 })
@@ -1,63 +1,956 @@
-use napi::bindgen_prelude::Result;
-use reqwest::header::{AUTHORIZATION, USER_AGENT};
-use serde::Deserialize;
+use async_trait::async_trait;
+use clap::ValueEnum;
+use futures::{future, stream, Future, StreamExt, TryStreamExt};
+use log::{debug, warn};
+use napi::bindgen_prelude::{Error as NapiError, Result};
+use reqwest::header::{
+  HeaderName, AUTHORIZATION, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, LINK,
+  USER_AGENT,
+};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
 
-use crate::error::reqwest_error;
+use crate::cache::{now, Cache, CacheEntry};
+use crate::error::{file_error, json_error, reqwest_error};
+use crate::forge::{Forge, ForgeKind, RepoUrls};
+use crate::gitlab::encode;
 
+const API_URL: &str = "https://api.github.com/";
+/// Maximum page size of GitHub's REST API
+const PER_PAGE: usize = 100;
+
+/// Sends all requests to the forge's API (GitHub by default).
+/// Responses can be recorded to or replayed from a directory.
+#[derive(Debug)]
+pub struct ApiClient {
+  client: reqwest::Client,
+  api_url: String,
+  auth_header: Option<(HeaderName, String)>,
+  record_dir: Option<PathBuf>,
+  replay_dir: Option<PathBuf>,
+  cache: Option<Cache>,
+}
+
+impl ApiClient {
+  pub fn new(gh_token: &str) -> Self {
+    Self {
+      client: reqwest::Client::new(),
+      api_url: API_URL.to_owned(),
+      auth_header: Some((AUTHORIZATION, format!("token {gh_token}"))),
+      record_dir: None,
+      replay_dir: None,
+      cache: None,
+    }
+  }
+
+  /// Send the requests to another API, e.g. GitHub Enterprise or GitLab
+  pub fn base_url(mut self, api_url: String) -> Self {
+    self.api_url = api_url;
+    self
+  }
+
+  /// Path of an absolute URL relative to the API, e.g. for links to the next page
+  pub fn relative_path(&self, url: &str) -> Option<String> {
+    url.strip_prefix(&self.api_url).map(str::to_owned)
+  }
+
+  /// Authenticate with another header than GitHub's `Authorization: token ...`
+  pub fn auth_header(mut self, name: HeaderName, value: String) -> Self {
+    self.auth_header = Some((name, value));
+    self
+  }
+
+  /// Send the requests without any credentials, GitHub limits these to 60 requests per hour
+  pub fn anonymous(mut self) -> Self {
+    self.auth_header = None;
+    self
+  }
+
+  pub fn is_anonymous(&self) -> bool {
+    self.auth_header.is_none()
+  }
+
+  /// Cache responses on disk and revalidate them with conditional requests
+  pub fn cache(mut self, cache: Cache) -> Self {
+    self.cache = Some(cache);
+    self
+  }
+
+  /// Save every raw response as a fixture into `dir`
+  pub fn record(mut self, dir: PathBuf) -> Self {
+    self.record_dir = Some(dir);
+    self
+  }
+
+  /// Serve every response from the fixtures in `dir` instead of the network
+  pub fn replay(mut self, dir: PathBuf) -> Self {
+    self.replay_dir = Some(dir);
+    self
+  }
+
+  pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+    let response = self.load(path, self.fetch(path)).await?;
+
+    serde_json::from_str(&response.body).map_err(json_error)
+  }
+
+  /// Like `get`, but `None` if the resource doesn't exist
+  pub async fn get_optional<T: DeserializeOwned>(&self, path: &str) -> Result<Option<T>> {
+    let response = self.load(path, self.fetch(path)).await?;
+
+    if response.is_not_found() {
+      return Ok(None);
+    }
+
+    serde_json::from_str(&response.body)
+      .map(Some)
+      .map_err(json_error)
+  }
+
+  /// Like `get`, but also returns the pagination headers of the response
+  pub async fn get_page<T: DeserializeOwned>(&self, path: &str) -> Result<(T, Pagination)> {
+    let response = self.load(path, self.fetch(path)).await?;
+    let data = serde_json::from_str(&response.body).map_err(json_error)?;
+
+    Ok((data, response.pagination))
+  }
+
+  /// Like `get_page`, but `None` if the resource doesn't exist
+  pub async fn get_page_optional<T: DeserializeOwned>(
+    &self,
+    path: &str,
+  ) -> Result<Option<(T, Pagination)>> {
+    let response = self.load(path, self.fetch(path)).await?;
+
+    if response.is_not_found() {
+      return Ok(None);
+    }
+
+    let data = serde_json::from_str(&response.body).map_err(json_error)?;
+
+    Ok(Some((data, response.pagination)))
+  }
+
+  /// Sends a GraphQL query. These requests aren't cached, but can be recorded and replayed.
+  pub async fn graphql<T: DeserializeOwned>(
+    &self,
+    query: &str,
+    variables: serde_json::Value,
+  ) -> Result<T> {
+    let key = format!("graphql?{variables}");
+    let request = async {
+      self
+        .authorize(self.client.post(format!("{}graphql", self.api_url)))
+        .json(&serde_json::json!({ "query": query, "variables": variables }))
+        .send()
+        .await
+        .map_err(reqwest_error)?
+        .text()
+        .await
+        .map_err(reqwest_error)
+        .map(RawResponse::from)
+    };
+    let body = self.load(&key, request).await?.body;
+
+    let response: GraphQlResponse<T> = serde_json::from_str(&body).map_err(json_error)?;
+
+    match (response.data, response.errors) {
+      (Some(data), None) => Ok(data),
+      // Objects that don't exist are null in the data, e.g. a PR number that belongs to an issue
+      (Some(data), Some(errors)) if errors.iter().all(GraphQlError::is_not_found) => {
+        for error in errors {
+          debug!("Ignoring GraphQL error: {}", error.message);
+        }
+        Ok(data)
+      }
+      (_, errors) => {
+        let messages = errors
+          .unwrap_or_default()
+          .into_iter()
+          .map(|e| e.message)
+          .collect::<Vec<_>>()
+          .join(", ");
+        Err(NapiError::from_reason(format!(
+          "GraphQL request failed: {messages}"
+        )))
+      }
+    }
+  }
+
+  /// Serves the response from the replay directory or sends the request, and records it if needed
+  /// Fixtures only contain the body, so replayed responses have no pagination headers.
+  async fn load(
+    &self,
+    key: &str,
+    request: impl Future<Output = Result<RawResponse>>,
+  ) -> Result<RawResponse> {
+    let response = match &self.replay_dir {
+      Some(dir) => {
+        let fixture = response_path(dir, key);
+        fs::read_to_string(&fixture)
+          .map_err(|e| file_error(&fixture, e))?
+          .into()
+      }
+      None => request.await?,
+    };
+
+    if let Some(dir) = &self.record_dir {
+      let fixture = response_path(dir, key);
+      fs::create_dir_all(dir).map_err(|e| file_error(dir, e))?;
+      fs::write(&fixture, &response.body).map_err(|e| file_error(&fixture, e))?;
+    }
+
+    Ok(response)
+  }
+
+  /// Sends a HEAD request to inspect the headers and the final URL after redirects.
+  /// Returns `None` when replaying, as fixtures only contain bodies.
+  pub async fn head(&self, path: &str) -> Result<Option<reqwest::Response>> {
+    if self.replay_dir.is_some() {
+      return Ok(None);
+    }
+
+    self
+      .authorize(self.client.head(format!("{}{path}", self.api_url)))
+      .send()
+      .await
+      .map(Some)
+      .map_err(reqwest_error)
+  }
+
+  fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    let request = request.header(USER_AGENT, "thanks-contributors");
+
+    match &self.auth_header {
+      Some((name, value)) => request.header(name.clone(), value),
+      None => request,
+    }
+  }
+
+  /// Key of a response in the cache, a hash of the exact URL. The same path returns different responses
+  /// for other tokens (e.g. only the public members of an organization), so the credentials are part of it.
+  /// Only the hash ends up on disk, as the cache is stored in plain text.
+  fn cache_key(&self, path: &str) -> String {
+    let identity = match &self.auth_header {
+      Some((name, value)) => format!("{name}: {value}"),
+      None => "anonymous".to_owned(),
+    };
+
+    stable_hash(&format!("{identity}\n{}{path}", self.api_url))
+  }
+
+  async fn fetch(&self, path: &str) -> Result<RawResponse> {
+    let key = self.cache_key(path);
+    let cached = self.cache.as_ref().and_then(|c| c.read(&key));
+
+    if let (Some(cache), Some(entry)) = (&self.cache, &cached) {
+      if entry.is_fresh(cache.ttl(), now()) {
+        debug!("Using cached response for {path}");
+        return Ok(RawResponse {
+          body: entry.body.clone(),
+          pagination: entry.pagination.clone(),
+          status: Some(StatusCode::OK),
+        });
+      }
+    }
+
+    let mut request = self.authorize(self.client.get(format!("{}{path}", self.api_url)));
+
+    if let Some(entry) = &cached {
+      if let Some(etag) = &entry.etag {
+        request = request.header(IF_NONE_MATCH, etag);
+      }
+      if let Some(last_modified) = &entry.last_modified {
+        request = request.header(IF_MODIFIED_SINCE, last_modified);
+      }
+    }
+
+    let response = request.send().await.map_err(reqwest_error)?;
+    let status = Some(response.status());
+
+    let header = |name: &str| {
+      response
+        .headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned)
+    };
+    let pagination = Pagination {
+      link: header(LINK.as_str()),
+      total_count: header("x-total-count").and_then(|v| v.parse().ok()),
+    };
+
+    let Some(cache) = &self.cache else {
+      let body = response.text().await.map_err(reqwest_error)?;
+      return Ok(RawResponse {
+        body,
+        pagination,
+        status,
+      });
+    };
+
+    // 304 responses don't count against the rate limit
+    if let (StatusCode::NOT_MODIFIED, Some(mut entry)) = (response.status(), cached) {
+      debug!("Cached response for {path} is still valid");
+      entry.fetched_at = now();
+      if let Err(e) = cache.write(&key, &entry) {
+        warn!("Failed to update the cache for {path}: {e}");
+      }
+
+      return Ok(RawResponse {
+        body: entry.body,
+        pagination: entry.pagination,
+        status: Some(StatusCode::OK),
+      });
+    }
+
+    let is_success = response.status().is_success();
+    let etag = header(ETAG.as_str());
+    let last_modified = header(LAST_MODIFIED.as_str());
+    let body = response.text().await.map_err(reqwest_error)?;
+
+    if is_success {
+      let entry = CacheEntry {
+        etag,
+        last_modified,
+        fetched_at: now(),
+        body,
+        pagination,
+      };
+      if let Err(e) = cache.write(&key, &entry) {
+        warn!("Failed to write the cache for {path}: {e}");
+      }
+
+      return Ok(RawResponse {
+        body: entry.body,
+        pagination: entry.pagination,
+        status,
+      });
+    }
+
+    Ok(RawResponse {
+      body,
+      pagination,
+      status,
+    })
+  }
+}
+
+/// Body of a response together with its pagination headers
+#[derive(Debug, Default)]
+pub struct RawResponse {
+  pub body: String,
+  pub pagination: Pagination,
+  /// Unknown for replayed responses, as fixtures only contain the body
+  pub status: Option<StatusCode>,
+}
+
+impl RawResponse {
+  pub fn is_not_found(&self) -> bool {
+    match self.status {
+      Some(status) => status == StatusCode::NOT_FOUND,
+      // Recorded error responses only have GitHub's error message left
+      None => {
+        serde_json::from_str::<ErrorResponse>(&self.body).is_ok_and(|e| e.message == "Not Found")
+      }
+    }
+  }
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+  message: String,
+}
+
+impl From<String> for RawResponse {
+  fn from(body: String) -> Self {
+    Self {
+      body,
+      ..Default::default()
+    }
+  }
+}
+
+/// The `Link` and `X-Total-Count` headers of a paginated response
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Pagination {
+  pub link: Option<String>,
+  pub total_count: Option<usize>,
+}
+
+impl Pagination {
+  /// Whether there is another page after `fetched` items. `None` if the headers are missing.
+  pub fn has_next(&self, fetched: usize) -> Option<bool> {
+    if let Some(link) = &self.link {
+      return Some(link.contains(r#"rel="next""#));
+    }
+
+    self.total_count.map(|total| fetched < total)
+  }
+}
+
+/// Longest file name before the extension, most file systems allow 255 bytes
+const MAX_NAME_LEN: usize = 200;
+
+/// Turns e.g. `orgs/gatsbyjs/members?per_page=100` into `orgs_gatsbyjs_members_per_page_100_<hash>.json`.
+/// The readable part is lossy (e.g. `a/b` and `a_b`) and cut for long paths (e.g. GraphQL queries for many commits),
+/// so the hash of the exact path keeps the names unique.
+pub fn response_path(dir: &Path, path: &str) -> PathBuf {
+  let mut name: String = path
+    .chars()
+    .map(|c| match c {
+      'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' => c,
+      _ => '_',
+    })
+    .collect();
+  name.truncate(MAX_NAME_LEN - 17);
+
+  dir.join(format!("{name}_{}.json", stable_hash(path)))
+}
+
+/// 64-bit FNV-1a hash as hex. Unlike `DefaultHasher`, it's the same across Rust releases,
+/// so it can be used for names of files that outlive a build.
+pub fn stable_hash(input: &str) -> String {
+  let hash = input.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+    (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+  });
+
+  format!("{hash:016x}")
+}
+
+/// Lists the commits between two points in the history of a repository
+#[async_trait]
+pub trait CommitSource: Send + Sync {
+  async fn list_commits(
+    &self,
+    owner: &str,
+    repo: &str,
+    base: &str,
+    head: &str,
+  ) -> Result<Vec<Commit>>;
+}
+
+/// Uses the REST endpoint to compare two commits
+pub struct RestCommitSource<'a> {
+  api: &'a ApiClient,
+}
+
+impl<'a> RestCommitSource<'a> {
+  pub fn new(api: &'a ApiClient) -> Self {
+    Self { api }
+  }
+}
+
+#[async_trait]
+impl CommitSource for RestCommitSource<'_> {
+  async fn list_commits(
+    &self,
+    owner: &str,
+    repo: &str,
+    base: &str,
+    head: &str,
+  ) -> Result<Vec<Commit>> {
+    compare_commits(owner, repo, base.to_owned(), head.to_owned(), self.api).await
+  }
+}
+
+/// GitHub (or GitHub Enterprise Server), with the commits coming from the REST or GraphQL API
+pub struct GitHubForge<'a> {
+  api: &'a ApiClient,
+  commit_source: Box<dyn CommitSource + 'a>,
+  web_url: String,
+}
+
+impl<'a> GitHubForge<'a> {
+  pub fn new(api: &'a ApiClient, commit_source: Box<dyn CommitSource + 'a>, web_url: &str) -> Self {
+    Self {
+      api,
+      commit_source,
+      web_url: web_url.trim_end_matches('/').to_owned(),
+    }
+  }
+}
+
+#[async_trait]
+impl Forge for GitHubForge<'_> {
+  async fn list_commits(
+    &self,
+    owner: &str,
+    repo: &str,
+    base: &str,
+    head: &str,
+  ) -> Result<Vec<Commit>> {
+    self
+      .commit_source
+      .list_commits(owner, repo, base, head)
+      .await
+  }
+
+  async fn list_commits_between(
+    &self,
+    owner: &str,
+    repo: &str,
+    branch: Option<&str>,
+    since: &str,
+    until: &str,
+  ) -> Result<Vec<Commit>> {
+    list_commits_between(owner, repo, branch, since, until, self.api).await
+  }
+
+  async fn list_members(&self, owner: &str, repo: Option<&str>) -> Result<Vec<String>> {
+    // Personal accounts have no members, but the owner and collaborators are insiders as well
+    if get_owner(owner, self.api).await?.kind == OwnerKind::User {
+      let mut members = vec![owner.to_owned()];
+      let Some(repo) = repo else {
+        return Ok(members);
+      };
+      match list_collaborators(owner, repo, None, self.api).await {
+        Ok(collaborators) => members.extend(collaborators.into_iter().filter(|c| c != owner)),
+        Err(e) => warn!(
+          "Failed to list the collaborators of {owner}/{repo}, only excluding {owner}: {}",
+          e.reason
+        ),
+      }
+      return Ok(members);
+    }
+
+    // Anonymous requests were already warned about
+    if !self.api.is_anonymous() && has_public_members_only(owner, self.api).await? {
+      // Printed even without -v, as it changes the result
+      eprintln!(
+        "Warning: The token can only see the public members of '{owner}', so private members will be listed as contributors. Use a token with the read:org scope or pass the members with --members-file."
+      );
+    }
+
+    list_members(owner, self.api).await
+  }
+
+  async fn list_collaborators(
+    &self,
+    owner: &str,
+    repo: &str,
+    permission: CollaboratorPermission,
+  ) -> Result<Vec<String>> {
+    list_collaborators(owner, repo, Some(permission), self.api).await
+  }
+
+  async fn list_team_members(&self, owner: &str, team: &str) -> Result<Vec<String>> {
+    list_team_members(owner, team, self.api).await
+  }
+
+  fn repo_urls(&self, owner: &str, repo: &str) -> RepoUrls {
+    RepoUrls::new(ForgeKind::GitHub, &self.web_url, owner, repo)
+  }
+
+  fn user_url(&self, login: &str) -> String {
+    format!("{}/{login}", self.web_url)
+  }
+}
+
+/// Commits of `base...head`, the oldest commit first. Unpaginated, the endpoint stops at 250 commits.
 pub async fn compare_commits(
   owner: &str,
   repo: &str,
   base: String,
   head: String,
-  gh_token: &str,
+  api: &ApiClient,
 ) -> Result<Vec<Commit>> {
-  let client = reqwest::Client::new();
-  let response = client
-    .get(format!(
-      "https://api.github.com/repos/{owner}/{repo}/compare/{base}...{head}"
-    ))
-    .header(USER_AGENT, "thanks-contributors")
-    .header(AUTHORIZATION, format!("token {gh_token}"))
-    .send()
-    .await
-    .map_err(reqwest_error)?
-    .json::<CompareCommitsResponse>()
-    .await
-    .map_err(reqwest_error)?;
+  let path = format!("repos/{owner}/{repo}/compare/{base}...{head}");
+  let mut commits = Vec::new();
+  let mut page = 1;
+
+  loop {
+    let (response, pagination) = api
+      .get_page::<CompareCommitsResponse>(&format!("{path}?{}", (PER_PAGE_PAGING.query)(page)))
+      .await?;
+    let count = response.commits.len();
+    commits.extend(response.commits);
+
+    // The total is part of the body, so this works for replayed responses as well
+    let has_next = pagination
+      .has_next(commits.len())
+      .unwrap_or(commits.len() < response.total_commits);
+    if !has_next || count == 0 {
+      break;
+    }
+    page += 1;
+  }
 
-  Ok(response.commits)
+  Ok(commits)
 }
 
-pub async fn list_members(owner: &str, gh_token: &str) -> Result<Vec<String>> {
-  let client = reqwest::Client::new();
-  let response = client
-    .get(format!(
-      "https://api.github.com/orgs/{owner}/members?per_page=100"
-    ))
-    .header(USER_AGENT, "thanks-contributors")
-    .header(AUTHORIZATION, format!("token {gh_token}"))
-    .send()
-    .await
-    .map_err(reqwest_error)?
-    .json::<Vec<Member>>()
-    .await
-    .map_err(reqwest_error)?;
+/// Commits of a branch (or the default branch) in a date range, the oldest commit first
+pub async fn list_commits_between(
+  owner: &str,
+  repo: &str,
+  branch: Option<&str>,
+  since: &str,
+  until: &str,
+  api: &ApiClient,
+) -> Result<Vec<Commit>> {
+  let mut path = format!("repos/{owner}/{repo}/commits?since={since}&until={until}");
+  if let Some(branch) = branch {
+    path.push_str(&format!("&sha={}", encode(branch)));
+  }
+
+  let mut commits = get_all_pages::<Commit>(&path, api).await?;
+  // The endpoint returns the newest commit first, unlike the compare endpoint
+  commits.reverse();
+
+  Ok(commits)
+}
+
+pub async fn list_members(owner: &str, api: &ApiClient) -> Result<Vec<String>> {
+  // Listing all members needs authentication, so fall back to the public ones
+  let endpoint = if api.is_anonymous() {
+    "public_members"
+  } else {
+    "members"
+  };
+  let response = get_all_pages::<Member>(&format!("orgs/{owner}/{endpoint}"), api).await?;
 
   let list_of_logins = response.into_iter().map(|m| m.login).collect();
 
   Ok(list_of_logins)
 }
 
+/// Account owning repositories, either a user or an organization
+pub async fn get_owner(owner: &str, api: &ApiClient) -> Result<Owner> {
+  api.get::<Owner>(&format!("users/{owner}")).await
+}
+
+/// Logins of the collaborators of a repository, optionally only those with at least `permission`.
+/// Listing them needs push access.
+pub async fn list_collaborators(
+  owner: &str,
+  repo: &str,
+  permission: Option<CollaboratorPermission>,
+  api: &ApiClient,
+) -> Result<Vec<String>> {
+  let collaborators =
+    get_all_pages::<Collaborator>(&format!("repos/{owner}/{repo}/collaborators"), api).await?;
+
+  // The permissions are cumulative, e.g. admins also have push set
+  let logins = collaborators
+    .into_iter()
+    .filter(|c| match permission {
+      Some(permission) => c
+        .permissions
+        .get(permission.as_str())
+        .copied()
+        .unwrap_or(false),
+      None => true,
+    })
+    .map(|c| c.login)
+    .collect();
+
+  Ok(logins)
+}
+
+/// Logins of the members of a team, `team` being its slug
+pub async fn list_team_members(owner: &str, team: &str, api: &ApiClient) -> Result<Vec<String>> {
+  let members = get_all_pages::<Member>(&format!("orgs/{owner}/teams/{team}/members"), api).await?;
+
+  Ok(members.into_iter().map(|m| m.login).collect())
+}
+
+/// How a list endpoint is paginated
+pub struct Paging {
+  /// Maximum number of items per page, a shorter page is the last one
+  pub page_size: usize,
+  /// Query parameters selecting a page, starting with page 1
+  pub query: fn(usize) -> String,
+}
+
+/// `per_page` & `page`, used by GitHub and GitLab
+pub const PER_PAGE_PAGING: Paging = Paging {
+  page_size: PER_PAGE,
+  query: |page| format!("per_page={PER_PAGE}&page={page}"),
+};
+
+/// Fetches every page of a list endpoint, following the `Link` header
+pub async fn get_all_pages<T: DeserializeOwned>(path: &str, api: &ApiClient) -> Result<Vec<T>> {
+  get_all_pages_with(path, &PER_PAGE_PAGING, api).await
+}
+
+/// Like `get_all_pages`, for endpoints with another kind of pagination
+pub async fn get_all_pages_with<T: DeserializeOwned>(
+  path: &str,
+  paging: &Paging,
+  api: &ApiClient,
+) -> Result<Vec<T>> {
+  get_optional_pages(path, paging, api)
+    .await?
+    .ok_or_else(|| NapiError::from_reason(format!("{path} doesn't exist")))
+}
+
+/// Like `get_all_pages`, but `None` if the list doesn't exist
+pub async fn get_all_pages_optional<T: DeserializeOwned>(
+  path: &str,
+  api: &ApiClient,
+) -> Result<Option<Vec<T>>> {
+  get_optional_pages(path, &PER_PAGE_PAGING, api).await
+}
+
+async fn get_optional_pages<T: DeserializeOwned>(
+  path: &str,
+  paging: &Paging,
+  api: &ApiClient,
+) -> Result<Option<Vec<T>>> {
+  let separator = if path.contains('?') { '&' } else { '?' };
+  let mut items = Vec::new();
+  let mut page = 1;
+
+  loop {
+    let Some((data, pagination)) = api
+      .get_page_optional::<Vec<T>>(&format!("{path}{separator}{}", (paging.query)(page)))
+      .await?
+    else {
+      return Ok(None);
+    };
+    let count = data.len();
+    items.extend(data);
+
+    // Without pagination headers (e.g. replayed responses) a full page means there might be more
+    let has_next = pagination
+      .has_next(items.len())
+      .unwrap_or(count == paging.page_size);
+    if !has_next || count == 0 {
+      break;
+    }
+    page += 1;
+  }
+
+  Ok(Some(items))
+}
+
+/// Scopes of classic tokens that can see the private members of an organization
+const ORG_SCOPES: [&str; 3] = ["read:org", "write:org", "admin:org"];
+
+/// Whether `orgs/{owner}/members` only returns the public members for the current token
+pub async fn has_public_members_only(owner: &str, api: &ApiClient) -> Result<bool> {
+  let Some(response) = api.head(&format!("orgs/{owner}/members")).await? else {
+    return Ok(false);
+  };
+
+  if !response.status().is_success() {
+    return Ok(false);
+  }
+
+  // GitHub redirects to the public members if the user doesn't belong to the organization
+  if response.url().path().ends_with("/public_members") {
+    return Ok(true);
+  }
+
+  // Only classic tokens list their scopes, fine-grained and App tokens have no such header
+  let missing_scope = response
+    .headers()
+    .get("x-oauth-scopes")
+    .and_then(|v| v.to_str().ok())
+    .is_some_and(|scopes| {
+      !scopes
+        .split(',')
+        .any(|scope| ORG_SCOPES.contains(&scope.trim()))
+    });
+
+  Ok(missing_scope)
+}
+
+pub async fn get_commit_date(
+  owner: &str,
+  repo: &str,
+  reference: &str,
+  api: &ApiClient,
+) -> Result<String> {
+  let response = api
+    .get::<SingleCommitResponse>(&format!("repos/{owner}/{repo}/commits/{reference}"))
+    .await?;
+
+  Ok(response.commit.committer.date)
+}
+
+/// Returns the logins that don't have any commit in the repository before `base`
+pub async fn find_first_time_contributors(
+  owner: &str,
+  repo: &str,
+  base: &str,
+  logins: Vec<String>,
+  api: &ApiClient,
+) -> Result<HashSet<String>> {
+  let until = get_commit_date(owner, repo, base, api).await?;
+  let until = &until;
+
+  let contributors: Vec<(String, bool)> = stream::iter(logins)
+    .map(|login| async move {
+      // Logins of bots like "renovate[bot]" need to be encoded
+      let response = api
+        .get::<Vec<serde_json::Value>>(&format!(
+          "repos/{owner}/{repo}/commits?author={author}&until={until}&per_page=1",
+          author = encode(&login)
+        ))
+        .await?;
+
+      Ok::<_, NapiError>((login, response.is_empty()))
+    })
+    .buffer_unordered(8)
+    .try_collect()
+    .await?;
+
+  Ok(
+    contributors
+      .into_iter()
+      .filter(|(_, is_first_time)| *is_first_time)
+      .map(|(login, _)| login)
+      .collect(),
+  )
+}
+
+/// Fetches the profile of every login (each login only once), with a few requests in parallel.
+/// Logins that can't be looked up (e.g. renamed or deleted accounts) are skipped.
+pub async fn fetch_users(logins: Vec<String>, api: &ApiClient) -> HashMap<String, User> {
+  let unique_logins: HashSet<String> = logins.into_iter().collect();
+
+  stream::iter(unique_logins)
+    .map(|login| async move {
+      match api.get::<User>(&format!("users/{}", encode(&login))).await {
+        Ok(user) => Some((login, user)),
+        Err(e) => {
+          warn!(
+            "Failed to fetch the profile of '{login}', only showing the login: {}",
+            e.reason
+          );
+          None
+        }
+      }
+    })
+    .buffer_unordered(8)
+    .filter_map(future::ready)
+    .collect()
+    .await
+}
+
+/// Labels of each PR, e.g. for the PRs the REST API doesn't resolve
+pub async fn fetch_pr_labels(
+  owner: &str,
+  repo: &str,
+  numbers: Vec<String>,
+  api: &ApiClient,
+) -> Result<HashMap<String, Vec<String>>> {
+  let unique_numbers: HashSet<String> = numbers.into_iter().collect();
+
+  stream::iter(unique_numbers)
+    .map(|number| async move {
+      // The number in a commit message might also be an issue or a deleted PR
+      let labels = api
+        .get_optional::<Vec<Label>>(&format!(
+          "repos/{owner}/{repo}/issues/{number}/labels?per_page=100"
+        ))
+        .await?
+        .unwrap_or_else(|| {
+          debug!("PR #{number} doesn't exist, treating it as having no labels");
+          Vec::new()
+        });
+
+      Ok((number, labels.into_iter().map(|l| l.name).collect()))
+    })
+    .buffer_unordered(8)
+    .try_collect()
+    .await
+}
+
+/// Everyone who reviewed each PR, possibly more than once
+pub async fn fetch_reviewers(
+  owner: &str,
+  repo: &str,
+  numbers: Vec<String>,
+  api: &ApiClient,
+) -> Result<HashMap<String, Vec<CommitAuthor>>> {
+  let unique_numbers: HashSet<String> = numbers.into_iter().collect();
+
+  stream::iter(unique_numbers)
+    .map(|number| async move {
+      // The number in a commit message might also be an issue or a deleted PR
+      let reviews: Vec<Review> =
+        get_all_pages_optional(&format!("repos/{owner}/{repo}/pulls/{number}/reviews"), api)
+          .await?
+          .unwrap_or_else(|| {
+            debug!("PR #{number} doesn't exist, treating it as having no reviews");
+            Vec::new()
+          });
+
+      // Reviews of deleted accounts have no user
+      Ok((number, reviews.into_iter().filter_map(|r| r.user).collect()))
+    })
+    .buffer_unordered(8)
+    .try_collect()
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GraphQlResponse<T> {
+  pub data: Option<T>,
+  pub errors: Option<Vec<GraphQlError>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GraphQlError {
+  pub message: String,
+  #[serde(rename = "type")]
+  pub kind: Option<String>,
+}
+
+impl GraphQlError {
+  pub fn is_not_found(&self) -> bool {
+    self.kind.as_deref() == Some("NOT_FOUND")
+  }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CompareCommitsResponse {
+  #[serde(default)]
+  pub total_commits: usize,
   pub commits: Vec<Commit>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Commit {
+  pub sha: String,
   pub url: String,
   pub commit: CommitMeta,
   pub author: Option<CommitAuthor>,
+  /// Only known if the backend resolves the PR of a commit (e.g. GraphQL)
+  #[serde(default)]
+  pub pull_request: Option<PullRequestRef>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Review {
+  pub user: Option<CommitAuthor>,
+}
+
+/// Issue closed by a PR
+#[derive(Clone, Debug)]
+pub struct Issue {
+  pub number: u64,
+  pub title: String,
+  pub author: Option<CommitAuthor>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Label {
+  pub name: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+pub struct PullRequestRef {
+  pub number: u64,
+  /// `None` if the backend didn't resolve them
+  #[serde(default)]
+  pub labels: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -70,9 +963,10 @@ pub struct CommitMeta {
 #[derive(Debug, Deserialize)]
 pub struct CommitMetaAuthor {
   pub name: String,
+  pub email: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct CommitAuthor {
   pub login: String,
   pub html_url: String,
@@ -82,3 +976,596 @@ pub struct CommitAuthor {
 pub struct Member {
   pub login: String,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct Collaborator {
+  pub login: String,
+  #[serde(default)]
+  pub permissions: HashMap<String, bool>,
+}
+
+/// Permissions of collaborators on GitHub, each including the ones before it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CollaboratorPermission {
+  Pull,
+  Triage,
+  Push,
+  Maintain,
+  Admin,
+}
+
+impl CollaboratorPermission {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      CollaboratorPermission::Pull => "pull",
+      CollaboratorPermission::Triage => "triage",
+      CollaboratorPermission::Push => "push",
+      CollaboratorPermission::Maintain => "maintain",
+      CollaboratorPermission::Admin => "admin",
+    }
+  }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Owner {
+  pub login: String,
+  #[serde(rename = "type")]
+  pub kind: OwnerKind,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+pub enum OwnerKind {
+  User,
+  Organization,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SingleCommitResponse {
+  pub commit: SingleCommitMeta,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SingleCommitMeta {
+  pub committer: SingleCommitCommitter,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SingleCommitCommitter {
+  pub date: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct User {
+  pub login: String,
+  pub name: Option<String>,
+  pub avatar_url: String,
+  pub company: Option<String>,
+  /// GitHub returns an empty string rather than null when it isn't set
+  pub blog: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_utils::{json_array, stub_server};
+  use std::time::Duration;
+
+  #[test]
+  fn pagination_has_next() {
+    let link = Pagination {
+      link: Some(
+        r#"<https://example.com/api/v1/orgs/acme/members?page=2>; rel="next""#.to_string(),
+      ),
+      total_count: Some(60),
+    };
+    let last = Pagination {
+      link: Some(
+        r#"<https://example.com/api/v1/orgs/acme/members?page=1>; rel="first""#.to_string(),
+      ),
+      total_count: Some(60),
+    };
+    let total = Pagination {
+      link: None,
+      total_count: Some(60),
+    };
+
+    assert_eq!(link.has_next(50), Some(true));
+    assert_eq!(last.has_next(50), Some(false));
+    assert_eq!(total.has_next(50), Some(true));
+    assert_eq!(total.has_next(60), Some(false));
+    assert_eq!(Pagination::default().has_next(50), None);
+  }
+
+  #[test]
+  fn response_path_correct() {
+    assert_eq!(
+      response_path(Path::new("fixtures"), "orgs/gatsbyjs/members?per_page=100"),
+      PathBuf::from("fixtures/orgs_gatsbyjs_members_per_page_100_0be37b1303439b10.json")
+    );
+    assert_eq!(
+      response_path(
+        Path::new("fixtures"),
+        "repos/gatsbyjs/gatsby/compare/gatsby@5.1.0-next.0...gatsby@5.1.0"
+      ),
+      PathBuf::from(
+        "fixtures/repos_gatsbyjs_gatsby_compare_gatsby_5.1.0-next.0...gatsby_5.1.0_b4ee86cf6fc263e4.json"
+      )
+    );
+    // Paths that only differ in characters replaced by `_`
+    assert_ne!(
+      response_path(
+        Path::new("fixtures"),
+        "repos/acme/widgets/commits?sha=feature/x"
+      ),
+      response_path(
+        Path::new("fixtures"),
+        "repos/acme/widgets/commits?sha=feature_x"
+      )
+    );
+  }
+
+  #[test]
+  fn stable_hash_correct() {
+    // Reference values of 64-bit FNV-1a
+    assert_eq!(stable_hash(""), "cbf29ce484222325");
+    assert_eq!(stable_hash("a"), "af63dc4c8601ec8c");
+  }
+
+  #[test]
+  fn response_path_long_path() {
+    // Only the end differs, which is cut off
+    let path = |last: char| format!("graphql?{}{last}", "a".repeat(4000));
+    let name = |path: &str| {
+      response_path(Path::new("fixtures"), path)
+        .file_name()
+        .unwrap()
+        .len()
+    };
+
+    assert_eq!(name(&path('a')), MAX_NAME_LEN + ".json".len());
+    assert_ne!(
+      response_path(Path::new("fixtures"), &path('a')),
+      response_path(Path::new("fixtures"), &path('b'))
+    );
+  }
+
+  #[tokio::test]
+  async fn cache_separates_apis_and_tokens() {
+    let members = |login: &str| format!(r#"[{{ "login": "{login}" }}]"#);
+    let first = stub_server(vec![(
+      "/api/v3/orgs/acme/members",
+      String::new(),
+      members("octocat"),
+    )]);
+    let second = stub_server(vec![(
+      "/api/v3/orgs/acme/members",
+      String::new(),
+      members("hubot"),
+    )]);
+    let dir = std::env::temp_dir().join(format!("thanks-contributors-test-cache-key-{}", now()));
+    // Without revalidation, a shared key would serve the response of the first API
+    let client = |url: &str, token: &str| {
+      ApiClient::new(token)
+        .base_url(ForgeKind::GitHub.api_url(url))
+        .cache(Cache::new(dir.clone(), Some(Duration::from_secs(3600))))
+    };
+    let first_login = |api: ApiClient| async move {
+      api.get::<Vec<Member>>("orgs/acme/members").await.unwrap()[0]
+        .login
+        .clone()
+    };
+
+    assert_eq!(first_login(client(&first, "token")).await, "octocat");
+    assert_eq!(first_login(client(&second, "token")).await, "hubot");
+
+    let api = client(&first, "token");
+    assert_ne!(api.cache_key("users/a@b"), api.cache_key("users/a_b"));
+    assert_ne!(
+      api.cache_key("orgs/acme/members"),
+      client(&first, "other-token").cache_key("orgs/acme/members")
+    );
+    assert_ne!(
+      api.cache_key("orgs/acme/members"),
+      client(&first, "")
+        .anonymous()
+        .cache_key("orgs/acme/members")
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[tokio::test]
+  async fn list_members_anonymous_uses_public_members() {
+    let url = stub_server(vec![(
+      "/api/v3/orgs/acme/public_members?per_page=100&page=1",
+      String::new(),
+      r#"[{ "login": "octocat" }]"#.to_string(),
+    )]);
+    let api = ApiClient::new("")
+      .base_url(ForgeKind::GitHub.api_url(&url))
+      .anonymous();
+
+    assert_eq!(list_members("acme", &api).await.unwrap(), vec!["octocat"]);
+  }
+
+  #[tokio::test]
+  async fn list_members_follows_pagination() {
+    let members = |range| json_array(range, |i| format!(r#"{{ "login": "member-{i}" }}"#));
+    let url = stub_server(vec![
+      (
+        "/api/v3/orgs/acme/members?per_page=100&page=1",
+        String::new(),
+        members(0..100),
+      ),
+      (
+        "/api/v3/orgs/acme/members?per_page=100&page=2",
+        String::new(),
+        members(100..150),
+      ),
+    ]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+
+    let logins = list_members("acme", &api).await.unwrap();
+
+    assert_eq!(logins.len(), 150);
+    assert_eq!(logins[149], "member-149");
+  }
+
+  #[tokio::test]
+  async fn has_public_members_only_checks_scopes() {
+    let url = stub_server(vec![
+      (
+        "/api/v3/orgs/acme/members",
+        "X-OAuth-Scopes: repo, read:org\r\n".to_string(),
+        String::new(),
+      ),
+      (
+        "/api/v3/orgs/initech/members",
+        "X-OAuth-Scopes: repo, user\r\n".to_string(),
+        String::new(),
+      ),
+      ("/api/v3/orgs/hooli/members", String::new(), String::new()),
+    ]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+
+    assert!(!has_public_members_only("acme", &api).await.unwrap());
+    assert!(has_public_members_only("initech", &api).await.unwrap());
+    // Fine-grained tokens don't expose their permissions
+    assert!(!has_public_members_only("hooli", &api).await.unwrap());
+  }
+
+  #[tokio::test]
+  async fn list_team_members_follows_pagination() {
+    let members = |range| json_array(range, |i| format!(r#"{{ "login": "member-{i}" }}"#));
+    let url = stub_server(vec![
+      (
+        "/api/v3/orgs/acme/teams/core/members?per_page=100&page=1",
+        "Link: </api/v3/orgs/acme/teams/core/members?per_page=100&page=2>; rel=\"next\"\r\n"
+          .to_string(),
+        members(0..100),
+      ),
+      (
+        "/api/v3/orgs/acme/teams/core/members?per_page=100&page=2",
+        String::new(),
+        members(100..120),
+      ),
+    ]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+
+    let logins = list_team_members("acme", "core", &api).await.unwrap();
+
+    assert_eq!(logins.len(), 120);
+    assert_eq!(logins[119], "member-119");
+  }
+
+  #[tokio::test]
+  async fn fetch_reviewers_follows_pagination() {
+    let reviews = |range| {
+      json_array(range, |i| {
+        format!(r#"{{ "user": {{ "login": "reviewer-{i}", "html_url": "" }} }}"#)
+      })
+    };
+    let url = stub_server(vec![
+      (
+        "/api/v3/repos/acme/widgets/pulls/1/reviews?per_page=100&page=1",
+        String::new(),
+        reviews(0..100),
+      ),
+      (
+        "/api/v3/repos/acme/widgets/pulls/1/reviews?per_page=100&page=2",
+        String::new(),
+        reviews(100..105),
+      ),
+    ]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+
+    let reviewers = fetch_reviewers("acme", "widgets", vec!["1".to_string()], &api)
+      .await
+      .unwrap();
+
+    assert_eq!(reviewers["1"].len(), 105);
+    assert_eq!(reviewers["1"][104].login, "reviewer-104");
+  }
+
+  #[tokio::test]
+  async fn fetch_users_includes_company_and_blog() {
+    let url = stub_server(vec![(
+      "/api/v3/users/octocat",
+      String::new(),
+      r#"{ "login": "octocat", "name": "The Octocat", "avatar_url": "https://avatars.githubusercontent.com/u/583231?v=4", "company": "@github", "blog": "https://github.blog" }"#
+        .to_string(),
+    )]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+
+    let users = fetch_users(vec!["octocat".to_string()], &api).await;
+
+    assert_eq!(users["octocat"].company.as_deref(), Some("@github"));
+    assert_eq!(
+      users["octocat"].blog.as_deref(),
+      Some("https://github.blog")
+    );
+  }
+
+  #[tokio::test]
+  async fn compare_commits_follows_pagination() {
+    let page = |range| {
+      let commits = json_array(range, |i| {
+        format!(
+          r#"{{ "sha": "sha-{i}", "url": "", "commit": {{ "url": "", "message": "Commit {i}", "author": {{ "name": "Harry", "email": "harry@example.com", "date": "2024-01-01T00:00:00Z" }} }}, "author": null }}"#
+        )
+      });
+      format!(r#"{{ "total_commits": 300, "commits": {commits} }}"#)
+    };
+    // Without pagination headers, `total_commits` tells whether there are more pages
+    let url = stub_server(vec![
+      (
+        "/api/v3/repos/acme/widgets/compare/v1.0.0...v1.1.0?per_page=100&page=1",
+        String::new(),
+        page(0..100),
+      ),
+      (
+        "/api/v3/repos/acme/widgets/compare/v1.0.0...v1.1.0?per_page=100&page=2",
+        String::new(),
+        page(100..200),
+      ),
+      (
+        "/api/v3/repos/acme/widgets/compare/v1.0.0...v1.1.0?per_page=100&page=3",
+        String::new(),
+        page(200..300),
+      ),
+    ]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+
+    let commits = compare_commits(
+      "acme",
+      "widgets",
+      "v1.0.0".to_string(),
+      "v1.1.0".to_string(),
+      &api,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(commits.len(), 300);
+    assert_eq!(commits[299].sha, "sha-299");
+  }
+
+  #[tokio::test]
+  async fn list_members_of_user_includes_collaborators() {
+    let url = stub_server(vec![
+      (
+        "/api/v3/users/octocat",
+        String::new(),
+        r#"{ "login": "octocat", "type": "User" }"#.to_string(),
+      ),
+      (
+        "/api/v3/repos/octocat/hello-world/collaborators?per_page=100&page=1",
+        String::new(),
+        r#"[{ "login": "octocat" }, { "login": "hubot" }]"#.to_string(),
+      ),
+    ]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+    let forge = GitHubForge::new(&api, Box::new(RestCommitSource::new(&api)), &url);
+
+    assert_eq!(
+      forge
+        .list_members("octocat", Some("hello-world"))
+        .await
+        .unwrap(),
+      vec!["octocat", "hubot"]
+    );
+  }
+
+  #[tokio::test]
+  async fn list_members_of_other_user_is_only_the_user() {
+    let url = stub_server(vec![(
+      "/api/v3/users/octocat",
+      String::new(),
+      r#"{ "login": "octocat", "type": "User" }"#.to_string(),
+    )]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+    let forge = GitHubForge::new(&api, Box::new(RestCommitSource::new(&api)), &url);
+
+    assert_eq!(
+      forge.list_members("octocat", None).await.unwrap(),
+      vec!["octocat"]
+    );
+  }
+
+  #[tokio::test]
+  async fn list_collaborators_filters_by_permission() {
+    let url = stub_server(vec![(
+      "/api/v3/repos/acme/widgets/collaborators?per_page=100&page=1",
+      String::new(),
+      r#"[
+        { "login": "admin", "permissions": { "admin": true, "maintain": true, "push": true, "triage": true, "pull": true } },
+        { "login": "writer", "permissions": { "admin": false, "maintain": false, "push": true, "triage": true, "pull": true } },
+        { "login": "reader", "permissions": { "admin": false, "maintain": false, "push": false, "triage": false, "pull": true } }
+      ]"#
+        .to_string(),
+    )]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+
+    assert_eq!(
+      list_collaborators("acme", "widgets", Some(CollaboratorPermission::Push), &api)
+        .await
+        .unwrap(),
+      vec!["admin", "writer"]
+    );
+    assert_eq!(
+      list_collaborators("acme", "widgets", None, &api)
+        .await
+        .unwrap()
+        .len(),
+      3
+    );
+  }
+
+  #[tokio::test]
+  async fn find_first_time_contributors_encodes_logins() {
+    let url = stub_server(vec![
+      (
+        "/api/v3/repos/acme/widgets/commits/v1.0.0",
+        String::new(),
+        r#"{ "commit": { "committer": { "date": "2024-01-01T00:00:00Z" } } }"#.to_string(),
+      ),
+      (
+        "/api/v3/repos/acme/widgets/commits?author=renovate%5Bbot%5D&until=2024-01-01T00:00:00Z&per_page=1",
+        String::new(),
+        "[]".to_string(),
+      ),
+      (
+        "/api/v3/repos/acme/widgets/commits?author=octocat&until=2024-01-01T00:00:00Z&per_page=1",
+        String::new(),
+        r#"[{ "sha": "abc" }]"#.to_string(),
+      ),
+    ]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+
+    let first_time_contributors = find_first_time_contributors(
+      "acme",
+      "widgets",
+      "v1.0.0",
+      vec!["renovate[bot]".to_string(), "octocat".to_string()],
+      &api,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+      first_time_contributors,
+      HashSet::from(["renovate[bot]".to_string()])
+    );
+  }
+
+  #[tokio::test]
+  async fn fetch_pr_labels_missing_pr_has_no_labels() {
+    let url = stub_server(vec![(
+      "/api/v3/repos/acme/widgets/issues/1/labels?per_page=100",
+      String::new(),
+      r#"[{ "name": "feature" }]"#.to_string(),
+    )]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+
+    let labels = fetch_pr_labels(
+      "acme",
+      "widgets",
+      vec!["1".to_string(), "404".to_string()],
+      &api,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+      labels,
+      HashMap::from([
+        ("1".to_string(), vec!["feature".to_string()]),
+        ("404".to_string(), vec![]),
+      ])
+    );
+  }
+
+  #[test]
+  fn is_not_found_replayed_error() {
+    let response = RawResponse::from(r#"{ "message": "Not Found" }"#.to_string());
+    assert!(response.is_not_found());
+
+    let response = RawResponse::from("[]".to_string());
+    assert!(!response.is_not_found());
+  }
+
+  #[tokio::test]
+  async fn fetch_reviewers_issue_has_no_reviews() {
+    let url = stub_server(vec![(
+      "/api/v3/repos/acme/widgets/pulls/1/reviews?per_page=100&page=1",
+      String::new(),
+      r#"[{ "user": { "login": "hermione", "html_url": "" } }]"#.to_string(),
+    )]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+
+    // #5 is an issue, so its reviews are a 404
+    let reviewers = fetch_reviewers(
+      "acme",
+      "widgets",
+      vec!["1".to_string(), "5".to_string()],
+      &api,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(reviewers["1"].len(), 1);
+    assert!(reviewers["5"].is_empty());
+  }
+
+  #[tokio::test]
+  async fn list_commits_between_oldest_first() {
+    let commit = |sha: &str| {
+      format!(
+        r#"{{ "sha": "{sha}", "url": "", "commit": {{ "url": "", "message": "Commit {sha}", "author": {{ "name": "Harry", "email": "harry@example.com" }} }}, "author": null }}"#
+      )
+    };
+    let url = stub_server(vec![(
+      "/api/v3/repos/acme/widgets/commits?since=2024-01-01T00:00:00Z&until=2024-02-01T00:00:00Z&sha=main&per_page=100&page=1",
+      String::new(),
+      format!("[{},{}]", commit("new"), commit("old")),
+    )]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+
+    let commits = list_commits_between(
+      "acme",
+      "widgets",
+      Some("main"),
+      "2024-01-01T00:00:00Z",
+      "2024-02-01T00:00:00Z",
+      &api,
+    )
+    .await
+    .unwrap();
+
+    let shas: Vec<_> = commits.iter().map(|c| c.sha.as_str()).collect();
+    assert_eq!(shas, vec!["old", "new"]);
+  }
+
+  #[tokio::test]
+  async fn list_commits_between_encodes_the_branch() {
+    let url = stub_server(vec![(
+      "/api/v3/repos/acme/widgets/commits?since=2024-01-01T00:00:00Z&until=2024-02-01T00:00:00Z&sha=feature%2Fgears%26more&per_page=100&page=1",
+      String::new(),
+      "[]".to_string(),
+    )]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+
+    let commits = list_commits_between(
+      "acme",
+      "widgets",
+      Some("feature/gears&more"),
+      "2024-01-01T00:00:00Z",
+      "2024-02-01T00:00:00Z",
+      &api,
+    )
+    .await
+    .unwrap();
+
+    assert!(commits.is_empty());
+  }
+}
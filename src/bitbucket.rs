@@ -0,0 +1,616 @@
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use log::warn;
+use napi::bindgen_prelude::Result;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::api::{ApiClient, Commit, CommitAuthor, CommitMeta, CommitMetaAuthor, PullRequestRef};
+use crate::forge::{credit_pull_request_authors, Forge, ForgeKind, MergedPullRequest, RepoUrls};
+
+/// Number of items per page, the maximum of both APIs
+const PAGE_LIMIT: usize = 100;
+
+/// PR of a merge commit, from subjects like "Merged in fix/gears (pull request #123)" or
+/// "Pull request #123: Fix gears" (Cloud) and "Merge pull request #123 in ACME/widgets from ..." (Server).
+/// References in the body are ignored, as they might mention any PR.
+pub fn parse_pull_request_ref(message: &str) -> Option<u64> {
+  lazy_static! {
+    // Regex is not dynamic so .unwrap is fine
+    static ref RE: Regex = Regex::new(
+      r"^(?:Merged in \S+ \(pull request #(?P<merged>[0-9]+)\)$|Pull request #(?P<titled>[0-9]+): |Merge pull request #(?P<server>[0-9]+) in )"
+    )
+    .unwrap();
+  }
+
+  let subject = message.lines().next()?;
+  let caps = RE.captures(subject)?;
+
+  ["merged", "titled", "server"]
+    .into_iter()
+    .find_map(|name| caps.name(name))
+    .and_then(|m| m.as_str().parse().ok())
+}
+
+/// Splits Bitbucket Cloud's raw author `Name <email>`
+pub fn parse_raw_author(raw: &str) -> (String, String) {
+  match raw.split_once('<') {
+    Some((name, email)) => (
+      name.trim().to_string(),
+      email.trim_end_matches('>').trim().to_string(),
+    ),
+    None => (raw.trim().to_string(), String::new()),
+  }
+}
+
+/// Bitbucket Cloud. `owner` is the workspace, `repo` the repository slug.
+pub struct BitbucketCloudForge<'a> {
+  api: &'a ApiClient,
+  web_url: String,
+}
+
+impl<'a> BitbucketCloudForge<'a> {
+  pub fn new(api: &'a ApiClient, web_url: &str) -> Self {
+    Self {
+      api,
+      web_url: web_url.trim_end_matches('/').to_owned(),
+    }
+  }
+
+  /// Follows the `next` links of Bitbucket Cloud's paginated responses
+  async fn get_all<T: serde::de::DeserializeOwned + Send>(&self, path: String) -> Result<Vec<T>> {
+    let mut values = Vec::new();
+    let mut next = Some(path);
+
+    while let Some(path) = next {
+      let page = self.api.get::<CloudPage<T>>(&path).await?;
+      values.extend(page.values);
+
+      next = page.next.and_then(|url| {
+        let path = self.api.relative_path(&url);
+        if path.is_none() {
+          warn!("Stopped paginating, the next page is on another host: {url}");
+        }
+        path
+      });
+    }
+
+    Ok(values)
+  }
+}
+
+#[async_trait]
+impl Forge for BitbucketCloudForge<'_> {
+  async fn list_commits(
+    &self,
+    owner: &str,
+    repo: &str,
+    base: &str,
+    head: &str,
+  ) -> Result<Vec<Commit>> {
+    let mut commits: Vec<Commit> = self
+      .get_all::<CloudCommit>(format!(
+        "repositories/{owner}/{repo}/commits/{head}?exclude={base}&pagelen={PAGE_LIMIT}"
+      ))
+      .await?
+      .into_iter()
+      .map(Commit::from)
+      .collect();
+
+    // Bitbucket returns the newest commit first
+    commits.reverse();
+
+    credit_pull_request_authors(&mut commits, |id| async move {
+      let pull_request = self
+        .api
+        .get_optional::<CloudPullRequest>(&format!("repositories/{owner}/{repo}/pullrequests/{id}"))
+        .await?;
+
+      Ok(pull_request.map(|pr| MergedPullRequest {
+        title: pr.title,
+        author: CommitMetaAuthor {
+          name: pr.author.display_name,
+          email: String::new(),
+        },
+        account: Some(CommitAuthor {
+          login: pr.author.nickname,
+          html_url: pr.author.links.html.href,
+        }),
+      }))
+    })
+    .await?;
+
+    Ok(commits)
+  }
+
+  async fn list_members(&self, owner: &str, _repo: Option<&str>) -> Result<Vec<String>> {
+    let members = self
+      .get_all::<CloudMember>(format!("workspaces/{owner}/members?pagelen={PAGE_LIMIT}"))
+      .await?;
+
+    Ok(
+      members
+        .into_iter()
+        .flat_map(|m| [m.user.nickname, m.user.display_name])
+        .collect(),
+    )
+  }
+
+  fn repo_urls(&self, owner: &str, repo: &str) -> RepoUrls {
+    RepoUrls::new(ForgeKind::Bitbucket, &self.web_url, owner, repo)
+  }
+
+  fn user_url(&self, login: &str) -> String {
+    format!("{}/{login}", self.web_url)
+  }
+}
+
+/// Bitbucket Server/Data Center. `owner` is the project key, `repo` the repository slug.
+pub struct BitbucketServerForge<'a> {
+  api: &'a ApiClient,
+  web_url: String,
+}
+
+impl<'a> BitbucketServerForge<'a> {
+  pub fn new(api: &'a ApiClient, web_url: &str) -> Self {
+    Self {
+      api,
+      web_url: web_url.trim_end_matches('/').to_owned(),
+    }
+  }
+
+  /// Follows `nextPageStart` of Bitbucket Server's paginated responses
+  async fn get_all<T: serde::de::DeserializeOwned + Send>(&self, path: &str) -> Result<Vec<T>> {
+    let separator = if path.contains('?') { '&' } else { '?' };
+    let mut values = Vec::new();
+    let mut start = 0;
+
+    loop {
+      let page = self
+        .api
+        .get::<ServerPage<T>>(&format!(
+          "{path}{separator}limit={PAGE_LIMIT}&start={start}"
+        ))
+        .await?;
+      values.extend(page.values);
+
+      match (page.is_last_page, page.next_page_start) {
+        (false, Some(next)) => start = next,
+        _ => break,
+      }
+    }
+
+    Ok(values)
+  }
+}
+
+#[async_trait]
+impl Forge for BitbucketServerForge<'_> {
+  async fn list_commits(
+    &self,
+    owner: &str,
+    repo: &str,
+    base: &str,
+    head: &str,
+  ) -> Result<Vec<Commit>> {
+    let web_url = &self.web_url;
+    let mut commits: Vec<Commit> = self
+      .get_all::<ServerCommit>(&format!(
+        "projects/{owner}/repos/{repo}/commits?since={base}&until={head}"
+      ))
+      .await?
+      .into_iter()
+      .map(|c| c.into_commit(web_url))
+      .collect();
+
+    // Bitbucket returns the newest commit first
+    commits.reverse();
+
+    credit_pull_request_authors(&mut commits, |id| async move {
+      let pull_request = self
+        .api
+        .get_optional::<ServerPullRequest>(&format!(
+          "projects/{owner}/repos/{repo}/pull-requests/{id}"
+        ))
+        .await?;
+
+      Ok(pull_request.map(|pr| {
+        let user = pr.author.user;
+        MergedPullRequest {
+          title: pr.title,
+          account: user.slug.as_ref().map(|slug| CommitAuthor {
+            login: slug.clone(),
+            html_url: format!("{web_url}/users/{slug}"),
+          }),
+          author: CommitMetaAuthor {
+            name: user.display_name.unwrap_or(user.name),
+            email: user.email_address.unwrap_or_default(),
+          },
+        }
+      }))
+    })
+    .await?;
+
+    Ok(commits)
+  }
+
+  async fn list_members(&self, owner: &str, _repo: Option<&str>) -> Result<Vec<String>> {
+    let members = self
+      .get_all::<ServerPermission>(&format!("projects/{owner}/permissions/users"))
+      .await?;
+
+    Ok(
+      members
+        .into_iter()
+        .flat_map(|m| [m.user.slug, m.user.display_name])
+        .collect(),
+    )
+  }
+
+  fn repo_urls(&self, owner: &str, repo: &str) -> RepoUrls {
+    RepoUrls::new(ForgeKind::BitbucketServer, &self.web_url, owner, repo)
+  }
+
+  fn user_url(&self, login: &str) -> String {
+    format!("{}/users/{login}", self.web_url)
+  }
+}
+
+impl From<CloudCommit> for Commit {
+  fn from(c: CloudCommit) -> Self {
+    let (name, email) = parse_raw_author(&c.author.raw);
+    let pull_request = parse_pull_request_ref(&c.message).map(|number| PullRequestRef {
+      number,
+      ..Default::default()
+    });
+
+    Commit {
+      sha: c.hash,
+      url: c.links.html.href.clone(),
+      commit: CommitMeta {
+        url: c.links.html.href,
+        message: c.message,
+        author: CommitMetaAuthor { name, email },
+      },
+      author: c.author.user.map(|u| CommitAuthor {
+        login: u.nickname,
+        html_url: u.links.html.href,
+      }),
+      pull_request,
+    }
+  }
+}
+
+impl ServerCommit {
+  fn into_commit(self, web_url: &str) -> Commit {
+    let pull_request = parse_pull_request_ref(&self.message).map(|number| PullRequestRef {
+      number,
+      ..Default::default()
+    });
+    let author = self.author.slug.as_ref().map(|slug| CommitAuthor {
+      login: slug.clone(),
+      html_url: format!("{web_url}/users/{slug}"),
+    });
+
+    Commit {
+      sha: self.id,
+      url: String::new(),
+      commit: CommitMeta {
+        url: String::new(),
+        message: self.message,
+        author: CommitMetaAuthor {
+          name: self.author.display_name.unwrap_or(self.author.name),
+          email: self.author.email_address.unwrap_or_default(),
+        },
+      },
+      author,
+      pull_request,
+    }
+  }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloudPage<T> {
+  pub values: Vec<T>,
+  pub next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloudCommit {
+  pub hash: String,
+  pub message: String,
+  pub author: CloudCommitAuthor,
+  pub links: CloudLinks,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloudCommitAuthor {
+  pub raw: String,
+  pub user: Option<CloudUser>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloudUser {
+  pub nickname: String,
+  pub display_name: String,
+  pub links: CloudLinks,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloudLinks {
+  pub html: CloudLink,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloudLink {
+  pub href: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloudPullRequest {
+  pub title: String,
+  pub author: CloudUser,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloudMember {
+  pub user: CloudUser,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerPage<T> {
+  pub values: Vec<T>,
+  pub is_last_page: bool,
+  pub next_page_start: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ServerCommit {
+  pub id: String,
+  pub message: String,
+  pub author: ServerUser,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerUser {
+  pub name: String,
+  pub email_address: Option<String>,
+  pub display_name: Option<String>,
+  /// Only set if the commit author is mapped to a Bitbucket user
+  pub slug: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ServerPullRequest {
+  pub title: String,
+  pub author: ServerParticipant,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ServerParticipant {
+  pub user: ServerUser,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerMember {
+  pub slug: String,
+  pub display_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ServerPermission {
+  pub user: ServerMember,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_utils::stub_server;
+
+  #[test]
+  fn parse_pull_request_ref_correct() {
+    assert_eq!(
+      parse_pull_request_ref("Merged in fix/gears (pull request #7)\n\nfix: Gears"),
+      Some(7)
+    );
+    assert_eq!(
+      parse_pull_request_ref("Pull request #8: fix: Gears\n\nMerge in ACME/widgets from fix"),
+      Some(8)
+    );
+    assert_eq!(
+      parse_pull_request_ref("Merge pull request #9 in ACME/widgets from fix to master"),
+      Some(9)
+    );
+    assert_eq!(parse_pull_request_ref("fix: Gears"), None);
+    // Only the subject of a merge commit counts
+    assert_eq!(
+      parse_pull_request_ref("fix: Gears\n\nSame as in (pull request #7)"),
+      None
+    );
+    assert_eq!(
+      parse_pull_request_ref("feat: Gears (pull request #7) and more"),
+      None
+    );
+  }
+
+  #[test]
+  fn parse_raw_author_correct() {
+    assert_eq!(
+      parse_raw_author("Harry Potter <harry@example.com>"),
+      ("Harry Potter".to_string(), "harry@example.com".to_string())
+    );
+    assert_eq!(
+      parse_raw_author("Harry Potter"),
+      ("Harry Potter".to_string(), String::new())
+    );
+  }
+
+  fn cloud_commit(hash: &str, message: &str, user: Option<&str>) -> String {
+    let user = match user {
+      Some(u) => format!(
+        r#"{{ "nickname": "{u}", "display_name": "{u}", "links": {{ "html": {{ "href": "https://bitbucket.org/{u}" }} }} }}"#
+      ),
+      None => "null".to_string(),
+    };
+
+    format!(
+      r#"{{ "hash": "{hash}", "message": "{message}", "author": {{ "raw": "Someone <someone@example.com>", "user": {user} }}, "links": {{ "html": {{ "href": "https://bitbucket.org/acme/widgets/commits/{hash}" }} }} }}"#
+    )
+  }
+
+  #[tokio::test]
+  async fn cloud_list_commits_follows_next() {
+    let url = stub_server(vec![
+      (
+        "/2.0/repositories/acme/widgets/commits/v1.1.0?exclude=v1.0.0&pagelen=100",
+        String::new(),
+        format!(
+          r#"{{ "values": [{}], "next": "{{url}}/2.0/repositories/acme/widgets/commits/v1.1.0?exclude=v1.0.0&pagelen=100&page=2" }}"#,
+          cloud_commit("c", "Merged in feat/gears (pull request #3)", Some("ron"))
+        ),
+      ),
+      (
+        "/2.0/repositories/acme/widgets/pullrequests/3",
+        String::new(),
+        r#"{ "title": "feat: Gears", "author": { "nickname": "harry", "display_name": "Harry Potter", "links": { "html": { "href": "https://bitbucket.org/harry" } } } }"#
+          .to_string(),
+      ),
+      (
+        "/2.0/repositories/acme/widgets/commits/v1.1.0?exclude=v1.0.0&pagelen=100&page=2",
+        String::new(),
+        format!(
+          r#"{{ "values": [{}, {}] }}"#,
+          cloud_commit("b", "fix: Sprockets", None),
+          cloud_commit("a", "chore: Setup", Some("ron"))
+        ),
+      ),
+    ]);
+    let api = ApiClient::new("token").base_url(format!("{url}/2.0/"));
+    let forge = BitbucketCloudForge::new(&api, "https://bitbucket.org");
+
+    let commits = forge
+      .list_commits("acme", "widgets", "v1.0.0", "v1.1.0")
+      .await
+      .unwrap();
+
+    let shas: Vec<_> = commits.iter().map(|c| c.sha.as_str()).collect();
+    assert_eq!(shas, vec!["a", "b", "c"]);
+    assert_eq!(commits[1].commit.author.name, "Someone");
+    assert!(commits[1].author.is_none());
+    assert_eq!(commits[2].pull_request.as_ref().map(|p| p.number), Some(3));
+    // The merge commit is credited to the PR's author instead of whoever merged it
+    assert_eq!(commits[2].commit.message, "feat: Gears");
+    assert_eq!(commits[2].author.as_ref().unwrap().login, "harry");
+  }
+
+  #[tokio::test]
+  async fn server_list_members_follows_next_page_start() {
+    let url = stub_server(vec![
+      (
+        "/rest/api/1.0/projects/ACME/permissions/users?limit=100&start=0",
+        String::new(),
+        r#"{ "values": [{ "user": { "slug": "harry", "displayName": "Harry Potter" } }], "isLastPage": false, "nextPageStart": 1 }"#
+          .to_string(),
+      ),
+      (
+        "/rest/api/1.0/projects/ACME/permissions/users?limit=100&start=1",
+        String::new(),
+        r#"{ "values": [{ "user": { "slug": "ron", "displayName": "Ron Weasley" } }], "isLastPage": true }"#
+          .to_string(),
+      ),
+    ]);
+    let api = ApiClient::new("token").base_url(ForgeKind::BitbucketServer.api_url(&url));
+    let forge = BitbucketServerForge::new(&api, &url);
+
+    assert_eq!(
+      forge.list_members("ACME", Some("widgets")).await.unwrap(),
+      vec!["harry", "Harry Potter", "ron", "Ron Weasley"]
+    );
+  }
+
+  #[tokio::test]
+  async fn server_list_commits_credits_the_pull_request_author() {
+    let url = stub_server(vec![
+      (
+        "/rest/api/1.0/projects/ACME/repos/widgets/commits?since=v1.0.0&until=v1.1.0&limit=100&start=0",
+        String::new(),
+        r#"{ "values": [{
+          "id": "0123456789abcdef",
+          "message": "Merge pull request #12 in ACME/widgets from fix to master",
+          "author": { "name": "ron", "displayName": "Ron Weasley", "slug": "ron" }
+        }], "isLastPage": true }"#
+          .to_string(),
+      ),
+      (
+        "/rest/api/1.0/projects/ACME/repos/widgets/pull-requests/12",
+        String::new(),
+        r#"{ "title": "fix: Gears", "author": { "user": { "name": "harry", "emailAddress": "harry@example.com", "displayName": "Harry Potter", "slug": "harry" } } }"#
+          .to_string(),
+      ),
+    ]);
+    let api = ApiClient::new("token").base_url(ForgeKind::BitbucketServer.api_url(&url));
+    let forge = BitbucketServerForge::new(&api, &url);
+
+    let commits = forge
+      .list_commits("ACME", "widgets", "v1.0.0", "v1.1.0")
+      .await
+      .unwrap();
+
+    assert_eq!(commits[0].commit.message, "fix: Gears");
+    assert_eq!(commits[0].commit.author.name, "Harry Potter");
+    assert_eq!(commits[0].author.as_ref().unwrap().login, "harry");
+  }
+
+  #[test]
+  fn server_commit_into_commit() {
+    let commit: ServerCommit = serde_json::from_str(
+      r#"{
+        "id": "0123456789abcdef",
+        "message": "Merge pull request #12 in ACME/widgets from fix to master\n\n* commit 'abc': (pull request #12)",
+        "author": { "name": "harry", "emailAddress": "harry@example.com", "displayName": "Harry Potter", "slug": "harry" }
+      }"#,
+    )
+    .unwrap();
+
+    let commit = commit.into_commit("https://bitbucket.example.com");
+
+    assert_eq!(commit.commit.author.name, "Harry Potter");
+    assert_eq!(
+      commit.author.map(|a| a.html_url),
+      Some("https://bitbucket.example.com/users/harry".to_string())
+    );
+    assert_eq!(commit.pull_request.map(|p| p.number), Some(12));
+  }
+
+  #[test]
+  fn repo_urls_bitbucket() {
+    let cloud = RepoUrls::new(
+      ForgeKind::Bitbucket,
+      "https://bitbucket.org",
+      "acme",
+      "widgets",
+    );
+    let server = RepoUrls::new(
+      ForgeKind::BitbucketServer,
+      "https://bitbucket.example.com",
+      "ACME",
+      "widgets",
+    );
+
+    assert_eq!(
+      cloud.pr_link("3"),
+      "[PR #3](https://bitbucket.org/acme/widgets/pull-requests/3)"
+    );
+    assert_eq!(
+      server.pr_link("3"),
+      "[PR #3](https://bitbucket.example.com/projects/ACME/repos/widgets/pull-requests/3)"
+    );
+    assert_eq!(
+      server.commit_link("0123456789abcdef"),
+      "[Commit 0123456](https://bitbucket.example.com/projects/ACME/repos/widgets/commits/0123456789abcdef)"
+    );
+  }
+}
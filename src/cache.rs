@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::api::Pagination;
+
+/// On-disk cache of API responses, revalidated with conditional requests
+#[derive(Debug)]
+pub struct Cache {
+  dir: PathBuf,
+  /// Responses younger than this are used without asking the API at all
+  ttl: Option<Duration>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CacheEntry {
+  pub etag: Option<String>,
+  pub last_modified: Option<String>,
+  /// Seconds since the UNIX epoch
+  pub fetched_at: u64,
+  pub body: String,
+  #[serde(default)]
+  pub pagination: Pagination,
+}
+
+impl CacheEntry {
+  pub fn is_fresh(&self, ttl: Option<Duration>, now: u64) -> bool {
+    match ttl {
+      Some(ttl) => now.saturating_sub(self.fetched_at) < ttl.as_secs(),
+      None => false,
+    }
+  }
+}
+
+/// `$XDG_CACHE_HOME/thanks-contributors` or the platform's equivalent
+pub fn default_cache_dir() -> Option<PathBuf> {
+  dirs::cache_dir().map(|d| d.join("thanks-contributors"))
+}
+
+pub fn now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or_default()
+}
+
+impl Cache {
+  pub fn new(dir: PathBuf, ttl: Option<Duration>) -> Self {
+    Self { dir, ttl }
+  }
+
+  pub fn ttl(&self) -> Option<Duration> {
+    self.ttl
+  }
+
+  /// `key` is a hash, so it can be used as the file name as is
+  fn path(&self, key: &str) -> PathBuf {
+    self.dir.join(format!("{key}.json"))
+  }
+
+  /// A missing or unreadable entry is treated like a cache miss
+  pub fn read(&self, key: &str) -> Option<CacheEntry> {
+    let content = fs::read_to_string(self.path(key)).ok()?;
+
+    serde_json::from_str(&content).ok()
+  }
+
+  /// Failing to write the cache shouldn't fail the whole run, so errors are only returned for logging
+  pub fn write(&self, key: &str, entry: &CacheEntry) -> std::io::Result<()> {
+    fs::create_dir_all(&self.dir)?;
+    let content = serde_json::to_string(entry)?;
+
+    fs::write(self.path(key), content)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn cache_entry_without_ttl_is_never_fresh() {
+    let entry = CacheEntry {
+      fetched_at: 100,
+      ..Default::default()
+    };
+
+    assert!(!entry.is_fresh(None, 100))
+  }
+
+  #[test]
+  fn cache_entry_within_ttl_is_fresh() {
+    let entry = CacheEntry {
+      fetched_at: 100,
+      ..Default::default()
+    };
+
+    assert!(entry.is_fresh(Some(Duration::from_secs(60)), 159));
+    assert!(!entry.is_fresh(Some(Duration::from_secs(60)), 160))
+  }
+
+  #[test]
+  fn cache_read_write_roundtrip() {
+    let dir = std::env::temp_dir().join(format!("thanks-contributors-test-{}", now()));
+    let cache = Cache::new(dir.clone(), None);
+    let entry = CacheEntry {
+      etag: Some("\"abc\"".to_string()),
+      last_modified: None,
+      fetched_at: 100,
+      body: "[]".to_string(),
+      ..Default::default()
+    };
+
+    assert_eq!(cache.read("0be37b1303439b10"), None);
+    cache.write("0be37b1303439b10", &entry).unwrap();
+    assert_eq!(cache.read("0be37b1303439b10"), Some(entry));
+
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
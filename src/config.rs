@@ -0,0 +1,63 @@
+use napi::bindgen_prelude::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{config_error, file_error};
+
+pub const DEFAULT_CONFIG_FILE: &str = "thanks-contributors.json";
+
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Config {
+  /// Canonical GitHub login mapped to other names, emails and logins of the same person
+  pub aliases: HashMap<String, Vec<String>>,
+}
+
+/// Reads the config file. A missing file is only an error if it was explicitly requested.
+pub fn load_config(path: &Path, is_explicit: bool) -> Result<Config> {
+  if !is_explicit && !path.exists() {
+    return Ok(Config::default());
+  }
+
+  let content = fs::read_to_string(path).map_err(|e| file_error(path, e))?;
+
+  serde_json::from_str(&content).map_err(|e| config_error(path, e))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_config_aliases() {
+    let config: Config =
+      serde_json::from_str(r#"{ "aliases": { "jdoe": ["John Doe", "jdoe@example.com"] } }"#)
+        .unwrap();
+
+    assert_eq!(
+      config.aliases,
+      HashMap::from([(
+        "jdoe".to_string(),
+        vec!["John Doe".to_string(), "jdoe@example.com".to_string()]
+      )])
+    )
+  }
+
+  #[test]
+  fn parse_config_empty() {
+    assert_eq!(
+      serde_json::from_str::<Config>("{}").unwrap(),
+      Config::default()
+    )
+  }
+
+  #[test]
+  fn load_config_missing_default() {
+    assert_eq!(
+      load_config(Path::new("does-not-exist.json"), false).unwrap(),
+      Config::default()
+    )
+  }
+}
@@ -1,4 +1,5 @@
 use std::env::VarError;
+use std::path::Path;
 
 use clap::CommandFactory;
 use napi::Error as NapiError;
@@ -10,13 +11,13 @@ pub fn format_cli_error<I: CommandFactory>(err: clap::Error) -> NapiError {
   NapiError::from_reason(format!("{reason}"))
 }
 
-pub fn env_var_error(err: VarError) -> NapiError {
+pub fn env_var_error(name: &str, err: VarError) -> NapiError {
   match err {
     VarError::NotPresent => {
-      NapiError::from_reason("Environment variable 'GITHUB_ACCESS_TOKEN' not present".to_owned())
+      NapiError::from_reason(format!("Environment variable '{name}' not present"))
     }
     VarError::NotUnicode(_) => {
-      NapiError::from_reason("Environment variable 'GITHUB_ACCESS_TOKEN' not unicode".to_owned())
+      NapiError::from_reason(format!("Environment variable '{name}' not unicode"))
     }
   }
 }
@@ -40,3 +41,24 @@ pub fn reqwest_error(err: reqwest::Error) -> NapiError {
     ))
   }
 }
+
+pub fn json_error(err: serde_json::Error) -> NapiError {
+  // Keep the same wording as reqwest's decode error
+  NapiError::from_reason(format!(
+    "Failed to parse response body: error decoding response body: {err}"
+  ))
+}
+
+pub fn file_error(path: &Path, err: std::io::Error) -> NapiError {
+  NapiError::from_reason(format!(
+    "Failed to read file '{path}': {err}",
+    path = path.display()
+  ))
+}
+
+pub fn config_error(path: &Path, err: serde_json::Error) -> NapiError {
+  NapiError::from_reason(format!(
+    "Failed to parse config file '{path}': {err}",
+    path = path.display()
+  ))
+}
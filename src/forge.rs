@@ -0,0 +1,387 @@
+use async_trait::async_trait;
+use clap::ValueEnum;
+use futures::future::{self, join_all};
+use futures::{stream, Future, StreamExt, TryStreamExt};
+use log::warn;
+use napi::bindgen_prelude::{Error as NapiError, Result};
+use std::collections::{HashMap, HashSet};
+
+use crate::api::{CollaboratorPermission, Commit, CommitAuthor, CommitMetaAuthor};
+
+/// Hosting platform of the repository
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ForgeKind {
+  #[clap(name = "github")]
+  GitHub,
+  #[clap(name = "gitlab")]
+  GitLab,
+  #[clap(name = "gitea", alias = "forgejo")]
+  Gitea,
+  /// Bitbucket Cloud
+  #[clap(name = "bitbucket")]
+  Bitbucket,
+  /// Bitbucket Server/Data Center
+  #[clap(name = "bitbucket-server")]
+  BitbucketServer,
+}
+
+impl ForgeKind {
+  /// Web URL of the public instance, used if no `--forge-url` is passed
+  pub fn default_url(&self) -> Option<&'static str> {
+    match self {
+      ForgeKind::GitHub => Some("https://github.com"),
+      ForgeKind::GitLab => Some("https://gitlab.com"),
+      ForgeKind::Gitea => Some("https://gitea.com"),
+      ForgeKind::Bitbucket => Some("https://bitbucket.org"),
+      // Only exists self-hosted
+      ForgeKind::BitbucketServer => None,
+    }
+  }
+
+  /// Base URL of the REST API for the given web URL
+  pub fn api_url(&self, web_url: &str) -> String {
+    let web_url = web_url.trim_end_matches('/');
+
+    match self {
+      ForgeKind::GitHub if web_url == "https://github.com" => "https://api.github.com/".to_string(),
+      // GitHub Enterprise Server
+      ForgeKind::GitHub => format!("{web_url}/api/v3/"),
+      ForgeKind::GitLab => format!("{web_url}/api/v4/"),
+      ForgeKind::Gitea => format!("{web_url}/api/v1/"),
+      ForgeKind::Bitbucket => "https://api.bitbucket.org/2.0/".to_string(),
+      ForgeKind::BitbucketServer => format!("{web_url}/rest/api/1.0/"),
+    }
+  }
+}
+
+/// Operations that differ between the hosting platforms
+#[async_trait]
+pub trait Forge: Send + Sync {
+  /// Commits between `base` and `head`, the oldest commit first
+  async fn list_commits(
+    &self,
+    owner: &str,
+    repo: &str,
+    base: &str,
+    head: &str,
+  ) -> Result<Vec<Commit>>;
+
+  /// Commits on `branch` (or the default branch) between two RFC 3339 timestamps, the oldest commit first
+  async fn list_commits_between(
+    &self,
+    _owner: &str,
+    _repo: &str,
+    _branch: Option<&str>,
+    _since: &str,
+    _until: &str,
+  ) -> Result<Vec<Commit>> {
+    Err(NapiError::from_reason(
+      "Date ranges are only supported on GitHub".to_owned(),
+    ))
+  }
+
+  /// Logins (and names, if commits aren't linked to accounts) of the members of the organization/group.
+  /// `repo` is set if `owner` owns the repository, to list its collaborators when `owner` is a personal account.
+  async fn list_members(&self, owner: &str, repo: Option<&str>) -> Result<Vec<String>>;
+
+  /// Logins of the collaborators of the repository with at least `permission`
+  async fn list_collaborators(
+    &self,
+    _owner: &str,
+    _repo: &str,
+    _permission: CollaboratorPermission,
+  ) -> Result<Vec<String>> {
+    Err(NapiError::from_reason(
+      "Collaborators are only supported on GitHub".to_owned(),
+    ))
+  }
+
+  /// Logins of the members of a team of the organization
+  async fn list_team_members(&self, _owner: &str, _team: &str) -> Result<Vec<String>> {
+    Err(NapiError::from_reason(
+      "Teams are only supported on GitHub".to_owned(),
+    ))
+  }
+
+  fn repo_urls(&self, owner: &str, repo: &str) -> RepoUrls;
+
+  fn user_url(&self, login: &str) -> String;
+}
+
+/// Members of all `orgs`, each listed once. Organizations whose members can't be listed are skipped with a warning,
+/// as that shouldn't stop the whole run.
+pub async fn list_members_of_orgs(
+  forge: &dyn Forge,
+  orgs: &[String],
+  owner: &str,
+  repo: &str,
+) -> Vec<String> {
+  let members = join_all(orgs.iter().map(|org| async move {
+    // The repository only belongs to one of them, other personal accounts have no collaborators to add
+    let repo = (org == owner).then_some(repo);
+    forge.list_members(org, repo).await.unwrap_or_else(|e| {
+      warn!(
+        "Failed to list the members of '{org}', they won't be excluded: {}",
+        e.reason
+      );
+      Vec::new()
+    })
+  }))
+  .await;
+
+  let mut members: Vec<String> = members.into_iter().flatten().collect();
+  // People can belong to several of the organizations
+  members.sort();
+  members.dedup();
+  members
+}
+
+/// Title and author of the PR/MR a merge commit belongs to
+pub struct MergedPullRequest {
+  pub title: String,
+  pub author: CommitMetaAuthor,
+  /// The author's account, if the forge links commits to accounts
+  pub account: Option<CommitAuthor>,
+}
+
+/// Merge commits are authored by whoever merged the PR/MR, so credit its author with its title instead.
+/// `fetch` looks up a PR/MR by its number, `None` if it doesn't exist (anymore).
+pub async fn credit_pull_request_authors<F, Fut>(commits: &mut [Commit], fetch: F) -> Result<()>
+where
+  F: Fn(u64) -> Fut,
+  Fut: Future<Output = Result<Option<MergedPullRequest>>>,
+{
+  let numbers: HashSet<u64> = commits
+    .iter()
+    .filter_map(|c| c.pull_request.as_ref().map(|pr| pr.number))
+    .collect();
+  let pull_requests: HashMap<u64, MergedPullRequest> = stream::iter(numbers)
+    .map(|number| {
+      let pull_request = fetch(number);
+      async move { Ok::<_, NapiError>(pull_request.await?.map(|pr| (number, pr))) }
+    })
+    .buffer_unordered(8)
+    .try_filter_map(|pr| future::ready(Ok(pr)))
+    .try_collect()
+    .await?;
+
+  for commit in commits {
+    let Some(pr) = commit
+      .pull_request
+      .as_ref()
+      .and_then(|pr| pull_requests.get(&pr.number))
+    else {
+      continue;
+    };
+
+    commit.commit.message = pr.title.clone();
+    commit.commit.author = CommitMetaAuthor {
+      name: pr.author.name.clone(),
+      email: pr.author.email.clone(),
+    };
+    commit.author = pr.account.clone();
+  }
+
+  Ok(())
+}
+
+/// Builds the links to PRs/MRs and commits of a repository
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RepoUrls {
+  kind: ForgeKind,
+  /// e.g. `https://github.com/owner/repo`
+  web_url: String,
+  /// Dates (`YYYY-MM-DD`) of the first and last commit, to search for PRs merged in between
+  merged_between: Option<(String, String)>,
+  /// Search qualifier matching the PRs of a person, e.g. `author` or `reviewed-by`
+  search_qualifier: Option<String>,
+}
+
+impl RepoUrls {
+  pub fn new(kind: ForgeKind, forge_url: &str, owner: &str, repo: &str) -> Self {
+    let forge_url = forge_url.trim_end_matches('/');
+    let web_url = match kind {
+      ForgeKind::BitbucketServer => format!("{forge_url}/projects/{owner}/repos/{repo}"),
+      _ => format!("{forge_url}/{owner}/{repo}"),
+    };
+
+    Self {
+      kind,
+      web_url,
+      merged_between: None,
+      search_qualifier: Some("author".to_owned()),
+    }
+  }
+
+  /// Limit searches to the PRs merged between the two dates
+  pub fn merged_between(mut self, since: &str, until: &str) -> Self {
+    self.merged_between = Some((since.to_owned(), until.to_owned()));
+    self
+  }
+
+  /// Search for the PRs a person is credited for with another qualifier, or don't link to a search at all
+  pub fn search_by(mut self, qualifier: Option<&str>) -> Self {
+    self.search_qualifier = qualifier.map(str::to_owned);
+    self
+  }
+
+  pub fn github(owner: &str, repo: &str) -> Self {
+    Self::new(ForgeKind::GitHub, "https://github.com", owner, repo)
+  }
+
+  /// How the forge refers to a PR/MR in text, e.g. `#123` or `!123`
+  pub fn reference(&self, number: &str) -> String {
+    match self.kind {
+      ForgeKind::GitHub | ForgeKind::Gitea | ForgeKind::Bitbucket | ForgeKind::BitbucketServer => {
+        format!("#{number}")
+      }
+      ForgeKind::GitLab => format!("!{number}"),
+    }
+  }
+
+  pub fn pr_link(&self, number: &str) -> String {
+    let web_url = &self.web_url;
+
+    match self.kind {
+      ForgeKind::GitHub => format!("[PR #{number}]({web_url}/pull/{number})"),
+      ForgeKind::GitLab => format!("[MR !{number}]({web_url}/-/merge_requests/{number})"),
+      ForgeKind::Gitea => format!("[PR #{number}]({web_url}/pulls/{number})"),
+      ForgeKind::Bitbucket | ForgeKind::BitbucketServer => {
+        format!("[PR #{number}]({web_url}/pull-requests/{number})")
+      }
+    }
+  }
+
+  /// Link to the merged PRs of an author (or reviewer), only supported on GitHub
+  pub fn pr_search_url(&self, login: &str) -> Option<String> {
+    let qualifier = self.search_qualifier.as_ref()?;
+    if self.kind != ForgeKind::GitHub {
+      return None;
+    }
+
+    let mut query = format!("is:pr+is:merged+{qualifier}:{login}");
+    if let Some((since, until)) = &self.merged_between {
+      query.push_str(&format!("+merged:{since}..{until}"));
+    }
+
+    Some(format!("{}/pulls?q={query}", self.web_url))
+  }
+
+  pub fn commit_link(&self, sha: &str) -> String {
+    let web_url = &self.web_url;
+    let short_sha = sha.get(..7).unwrap_or(sha);
+
+    match self.kind {
+      ForgeKind::GitHub | ForgeKind::Gitea => {
+        format!("[Commit {short_sha}]({web_url}/commit/{sha})")
+      }
+      ForgeKind::GitLab => format!("[Commit {short_sha}]({web_url}/-/commit/{sha})"),
+      ForgeKind::Bitbucket | ForgeKind::BitbucketServer => {
+        format!("[Commit {short_sha}]({web_url}/commits/{sha})")
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::api::{ApiClient, GitHubForge, RestCommitSource};
+  use crate::test_utils::stub_server;
+
+  #[tokio::test]
+  async fn list_members_of_orgs_merges_and_skips_failures() {
+    let org = |login: &str| format!(r#"{{ "login": "{login}", "type": "Organization" }}"#);
+    let url = stub_server(vec![
+      ("/api/v3/users/acme", String::new(), org("acme")),
+      ("/api/v3/users/initech", String::new(), org("initech")),
+      (
+        "/api/v3/orgs/acme/members?per_page=100&page=1",
+        String::new(),
+        r#"[{ "login": "octocat" }, { "login": "hubot" }]"#.to_string(),
+      ),
+      (
+        "/api/v3/orgs/initech/members?per_page=100&page=1",
+        String::new(),
+        r#"[{ "login": "hubot" }, { "login": "monalisa" }]"#.to_string(),
+      ),
+    ]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+    let forge = GitHubForge::new(&api, Box::new(RestCommitSource::new(&api)), &url);
+    // "hooli" doesn't exist, so its members are skipped
+    let orgs = ["acme", "initech", "hooli"].map(str::to_owned);
+
+    assert_eq!(
+      list_members_of_orgs(&forge, &orgs, "acme", "widgets").await,
+      vec!["hubot", "monalisa", "octocat"]
+    );
+  }
+
+  #[test]
+  fn api_url_correct() {
+    assert_eq!(
+      ForgeKind::GitHub.api_url("https://github.com"),
+      "https://api.github.com/"
+    );
+    assert_eq!(
+      ForgeKind::GitHub.api_url("https://github.example.com/"),
+      "https://github.example.com/api/v3/"
+    );
+    assert_eq!(
+      ForgeKind::GitLab.api_url("https://gitlab.example.com"),
+      "https://gitlab.example.com/api/v4/"
+    );
+  }
+
+  #[test]
+  fn repo_urls_gitlab() {
+    let urls = RepoUrls::new(ForgeKind::GitLab, "https://gitlab.com/", "group", "project");
+
+    assert_eq!(
+      urls.pr_link("12"),
+      "[MR !12](https://gitlab.com/group/project/-/merge_requests/12)"
+    );
+    assert_eq!(
+      urls.commit_link("0123456789abcdef"),
+      "[Commit 0123456](https://gitlab.com/group/project/-/commit/0123456789abcdef)"
+    );
+    assert_eq!(urls.reference("12"), "!12");
+  }
+
+  #[test]
+  fn repo_urls_pr_search_url() {
+    let urls = RepoUrls::github("owner", "repo");
+
+    assert_eq!(
+      urls.pr_search_url("octocat").unwrap(),
+      "https://github.com/owner/repo/pulls?q=is:pr+is:merged+author:octocat"
+    );
+    assert_eq!(
+      urls
+        .merged_between("2022-11-01", "2022-11-08")
+        .pr_search_url("octocat")
+        .unwrap(),
+      "https://github.com/owner/repo/pulls?q=is:pr+is:merged+author:octocat+merged:2022-11-01..2022-11-08"
+    );
+    assert_eq!(
+      RepoUrls::new(ForgeKind::GitLab, "https://gitlab.com", "group", "project")
+        .pr_search_url("octocat"),
+      None
+    );
+  }
+
+  #[test]
+  fn repo_urls_pr_search_url_other_qualifier() {
+    let urls = RepoUrls::github("owner", "repo");
+
+    assert_eq!(
+      urls
+        .clone()
+        .search_by(Some("reviewed-by"))
+        .pr_search_url("octocat")
+        .unwrap(),
+      "https://github.com/owner/repo/pulls?q=is:pr+is:merged+reviewed-by:octocat"
+    );
+    assert_eq!(urls.search_by(None).pr_search_url("octocat"), None);
+  }
+}
@@ -0,0 +1,144 @@
+use async_trait::async_trait;
+use napi::bindgen_prelude::Result;
+
+use crate::api::{get_all_pages_with, ApiClient, Commit, CompareCommitsResponse, Member, Paging};
+use crate::forge::{Forge, ForgeKind, RepoUrls};
+
+/// Number of items per page, Gitea's default maximum
+const PAGE_LIMIT: usize = 50;
+
+/// `page` & `limit` instead of `per_page` & `page`
+const PAGE_PAGING: Paging = Paging {
+  page_size: PAGE_LIMIT,
+  query: |page| format!("page={page}&limit={PAGE_LIMIT}"),
+};
+
+/// Gitea or Forgejo instance. Their API is close to GitHub's, but paginated with `page` & `limit`.
+pub struct GiteaForge<'a> {
+  api: &'a ApiClient,
+  web_url: String,
+}
+
+impl<'a> GiteaForge<'a> {
+  pub fn new(api: &'a ApiClient, web_url: &str) -> Self {
+    Self {
+      api,
+      web_url: web_url.trim_end_matches('/').to_owned(),
+    }
+  }
+}
+
+#[async_trait]
+impl Forge for GiteaForge<'_> {
+  async fn list_commits(
+    &self,
+    owner: &str,
+    repo: &str,
+    base: &str,
+    head: &str,
+  ) -> Result<Vec<Commit>> {
+    let response = self
+      .api
+      .get::<CompareCommitsResponse>(&format!("repos/{owner}/{repo}/compare/{base}...{head}"))
+      .await?;
+
+    Ok(response.commits)
+  }
+
+  async fn list_members(&self, owner: &str, _repo: Option<&str>) -> Result<Vec<String>> {
+    let members =
+      get_all_pages_with::<Member>(&format!("orgs/{owner}/members"), &PAGE_PAGING, self.api)
+        .await?;
+
+    Ok(members.into_iter().map(|m| m.login).collect())
+  }
+
+  fn repo_urls(&self, owner: &str, repo: &str) -> RepoUrls {
+    RepoUrls::new(ForgeKind::Gitea, &self.web_url, owner, repo)
+  }
+
+  fn user_url(&self, login: &str) -> String {
+    format!("{}/{login}", self.web_url)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_utils::{json_array, stub_server};
+
+  fn members(range: std::ops::Range<usize>) -> String {
+    json_array(range, |i| {
+      format!(r#"{{ "id": {i}, "login": "member-{i}" }}"#)
+    })
+  }
+
+  #[tokio::test]
+  async fn list_members_follows_pagination_headers() {
+    let url = stub_server(vec![
+      (
+        "/api/v1/orgs/acme/members?page=1&limit=50",
+        "Link: </api/v1/orgs/acme/members?page=2&limit=50>; rel=\"next\"\r\nX-Total-Count: 60\r\n"
+          .to_string(),
+        members(0..50),
+      ),
+      (
+        "/api/v1/orgs/acme/members?page=2&limit=50",
+        "Link: </api/v1/orgs/acme/members?page=1&limit=50>; rel=\"first\"\r\nX-Total-Count: 60\r\n"
+          .to_string(),
+        members(50..60),
+      ),
+    ]);
+    let api = ApiClient::new("token").base_url(ForgeKind::Gitea.api_url(&url));
+    let forge = GiteaForge::new(&api, &url);
+
+    let logins = forge.list_members("acme", Some("widgets")).await.unwrap();
+
+    assert_eq!(logins.len(), 60);
+    assert_eq!(logins[59], "member-59");
+  }
+
+  #[tokio::test]
+  async fn list_commits_correct() {
+    let url = stub_server(vec![(
+      "/api/v1/repos/acme/widgets/compare/v1.0.0...v1.1.0",
+      String::new(),
+      r#"{
+        "total_commits": 1,
+        "commits": [{
+          "sha": "0123456789abcdef",
+          "url": "https://gitea.example.com/api/v1/repos/acme/widgets/git/commits/0123456789abcdef",
+          "html_url": "https://gitea.example.com/acme/widgets/commit/0123456789abcdef",
+          "commit": {
+            "url": "https://gitea.example.com/api/v1/repos/acme/widgets/git/commits/0123456789abcdef",
+            "message": "feat: Add gears (#3)\n",
+            "author": { "name": "Harry", "email": "harry@example.com", "date": "2024-01-02T10:00:00Z" }
+          },
+          "author": { "id": 1, "login": "harry", "html_url": "https://gitea.example.com/harry" }
+        }]
+      }"#
+        .to_string(),
+    )]);
+    let api = ApiClient::new("token").base_url(ForgeKind::Gitea.api_url(&url));
+    let forge = GiteaForge::new(&api, &url);
+
+    let commits = forge
+      .list_commits("acme", "widgets", "v1.0.0", "v1.1.0")
+      .await
+      .unwrap();
+
+    assert_eq!(commits.len(), 1);
+    assert_eq!(commits[0].author.as_ref().unwrap().login, "harry");
+  }
+
+  #[test]
+  fn repo_urls_gitea() {
+    let api = ApiClient::new("token");
+    let forge = GiteaForge::new(&api, "https://gitea.example.com/");
+
+    assert_eq!(
+      forge.repo_urls("acme", "widgets").pr_link("3"),
+      "[PR #3](https://gitea.example.com/acme/widgets/pulls/3)"
+    );
+  }
+}
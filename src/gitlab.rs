@@ -0,0 +1,281 @@
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use napi::bindgen_prelude::Result;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::api::{get_all_pages, ApiClient, Commit, CommitMeta, CommitMetaAuthor, PullRequestRef};
+use crate::forge::{credit_pull_request_authors, Forge, ForgeKind, MergedPullRequest, RepoUrls};
+
+/// GitLab.com or a self-hosted GitLab instance. `owner` is the (sub)group path, `repo` the project.
+pub struct GitLabForge<'a> {
+  api: &'a ApiClient,
+  web_url: String,
+}
+
+impl<'a> GitLabForge<'a> {
+  pub fn new(api: &'a ApiClient, web_url: &str) -> Self {
+    Self {
+      api,
+      web_url: web_url.trim_end_matches('/').to_owned(),
+    }
+  }
+}
+
+#[async_trait]
+impl Forge for GitLabForge<'_> {
+  async fn list_commits(
+    &self,
+    owner: &str,
+    repo: &str,
+    base: &str,
+    head: &str,
+  ) -> Result<Vec<Commit>> {
+    let id = encode(&format!("{owner}/{repo}"));
+    let response = self
+      .api
+      .get::<GitLabCompareResponse>(&format!(
+        "projects/{id}/repository/compare?from={from}&to={to}",
+        from = encode(base),
+        to = encode(head),
+      ))
+      .await?;
+
+    let mut commits: Vec<Commit> = response.commits.into_iter().map(Commit::from).collect();
+
+    let id = &id;
+    credit_pull_request_authors(&mut commits, |iid| async move {
+      let merge_request = self
+        .api
+        .get_optional::<GitLabMergeRequest>(&format!("projects/{id}/merge_requests/{iid}"))
+        .await?;
+
+      Ok(merge_request.map(|mr| MergedPullRequest {
+        title: mr.title,
+        author: CommitMetaAuthor {
+          name: mr.author.name,
+          // Only the merge commit has an email, and that's the one of whoever merged it
+          email: String::new(),
+        },
+        // Commits on GitLab aren't linked to accounts either
+        account: None,
+      }))
+    })
+    .await?;
+
+    Ok(commits)
+  }
+
+  async fn list_members(&self, owner: &str, _repo: Option<&str>) -> Result<Vec<String>> {
+    let response = get_all_pages::<GitLabMember>(
+      &format!("groups/{id}/members", id = encode(owner)),
+      self.api,
+    )
+    .await?;
+
+    // Commits on GitLab aren't linked to accounts, so their authors can only be matched by name
+    Ok(
+      response
+        .into_iter()
+        .flat_map(|m| [m.username, m.name])
+        .collect(),
+    )
+  }
+
+  fn repo_urls(&self, owner: &str, repo: &str) -> RepoUrls {
+    RepoUrls::new(ForgeKind::GitLab, &self.web_url, owner, repo)
+  }
+
+  fn user_url(&self, login: &str) -> String {
+    format!("{}/{login}", self.web_url)
+  }
+}
+
+/// Percent-encodes everything except unreserved characters, e.g. `group/project` to `group%2Fproject`
+pub fn encode(input: &str) -> String {
+  input
+    .bytes()
+    .map(|b| match b {
+      b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+        (b as char).to_string()
+      }
+      _ => format!("%{b:02X}"),
+    })
+    .collect()
+}
+
+/// Merge commits contain e.g. "See merge request group/project!123" in their body
+pub fn parse_merge_request_ref(message: &str) -> Option<u64> {
+  lazy_static! {
+    // Regex is not dynamic so .unwrap is fine
+    static ref RE: Regex = Regex::new(r"See merge request \S*!(?P<mr>[0-9]+)").unwrap();
+  }
+
+  RE.captures(message)
+    .and_then(|caps| caps.name("mr"))
+    .and_then(|m| m.as_str().parse().ok())
+}
+
+impl From<GitLabCommit> for Commit {
+  fn from(c: GitLabCommit) -> Self {
+    let pull_request = parse_merge_request_ref(&c.message).map(|number| PullRequestRef {
+      number,
+      ..Default::default()
+    });
+
+    Commit {
+      sha: c.id,
+      url: c.web_url.clone(),
+      commit: CommitMeta {
+        url: c.web_url,
+        message: c.message,
+        author: CommitMetaAuthor {
+          name: c.author_name,
+          email: c.author_email,
+        },
+      },
+      author: None,
+      pull_request,
+    }
+  }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabCompareResponse {
+  pub commits: Vec<GitLabCommit>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabCommit {
+  pub id: String,
+  pub message: String,
+  pub author_name: String,
+  pub author_email: String,
+  pub web_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabMergeRequest {
+  pub title: String,
+  pub author: GitLabMember,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabMember {
+  pub username: String,
+  pub name: String,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_utils::{json_array, stub_server};
+
+  #[test]
+  fn encode_correct() {
+    assert_eq!(
+      encode("group/sub-group/project"),
+      "group%2Fsub-group%2Fproject"
+    );
+    assert_eq!(encode("v1.0.0"), "v1.0.0");
+    assert_eq!(encode("gatsby@5.1.0"), "gatsby%405.1.0");
+  }
+
+  #[test]
+  fn parse_merge_request_ref_correct() {
+    assert_eq!(
+      parse_merge_request_ref(
+        "Merge branch 'fix' into 'main'\n\nFix it\n\nSee merge request group/project!42"
+      ),
+      Some(42)
+    );
+    assert_eq!(parse_merge_request_ref("fix: Something"), None);
+  }
+
+  #[tokio::test]
+  async fn list_members_follows_pagination() {
+    let members = |range| {
+      json_array(range, |i| {
+        format!(r#"{{ "username": "member-{i}", "name": "Member {i}" }}"#)
+      })
+    };
+    let url = stub_server(vec![
+      (
+        "/api/v4/groups/acme/members?per_page=100&page=1",
+        "Link: </api/v4/groups/acme/members?per_page=100&page=2>; rel=\"next\"\r\n".to_string(),
+        members(0..100),
+      ),
+      (
+        "/api/v4/groups/acme/members?per_page=100&page=2",
+        String::new(),
+        members(100..110),
+      ),
+    ]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitLab.api_url(&url));
+    let forge = GitLabForge::new(&api, &url);
+
+    let members = forge.list_members("acme", Some("widgets")).await.unwrap();
+
+    // Both the username and the name of each member
+    assert_eq!(members.len(), 220);
+    assert_eq!(members[218], "member-109");
+  }
+
+  #[tokio::test]
+  async fn list_commits_credits_the_merge_request_author() {
+    let url = stub_server(vec![
+      (
+        "/api/v4/projects/acme%2Fwidgets/repository/compare?from=v1.0.0&to=v1.1.0",
+        String::new(),
+        r#"{ "commits": [{
+          "id": "0123456789abcdef",
+          "message": "Merge branch 'fix' into 'main'\n\nfix: Tighten sprockets\n\nSee merge request acme/widgets!22",
+          "author_name": "Ginny",
+          "author_email": "ginny@example.com",
+          "web_url": ""
+        }] }"#
+          .to_string(),
+      ),
+      (
+        "/api/v4/projects/acme%2Fwidgets/merge_requests/22",
+        String::new(),
+        r#"{ "title": "fix: Tighten sprockets", "author": { "username": "luna", "name": "Luna" } }"#
+          .to_string(),
+      ),
+    ]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitLab.api_url(&url));
+    let forge = GitLabForge::new(&api, &url);
+
+    let commits = forge
+      .list_commits("acme", "widgets", "v1.0.0", "v1.1.0")
+      .await
+      .unwrap();
+
+    assert_eq!(commits[0].commit.message, "fix: Tighten sprockets");
+    assert_eq!(commits[0].commit.author.name, "Luna");
+    assert_eq!(commits[0].pull_request.as_ref().map(|p| p.number), Some(22));
+  }
+
+  #[test]
+  fn gitlab_commit_into_commit() {
+    let commit: GitLabCommit = serde_json::from_str(
+      r#"{
+        "id": "0123456789abcdef",
+        "short_id": "0123456",
+        "title": "Merge branch 'fix' into 'main'",
+        "message": "Merge branch 'fix' into 'main'\n\nSee merge request group/project!42",
+        "author_name": "Author A",
+        "author_email": "author-a@example.com",
+        "web_url": "https://gitlab.com/group/project/-/commit/0123456789abcdef"
+      }"#,
+    )
+    .unwrap();
+
+    let commit = Commit::from(commit);
+
+    assert_eq!(commit.sha, "0123456789abcdef");
+    assert_eq!(commit.commit.author.name, "Author A");
+    assert!(commit.author.is_none());
+    assert_eq!(commit.pull_request.map(|p| p.number), Some(42));
+  }
+}
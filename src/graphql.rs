@@ -0,0 +1,339 @@
+use async_trait::async_trait;
+use futures::{stream, StreamExt, TryStreamExt};
+use napi::bindgen_prelude::{Error as NapiError, Result};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+
+use crate::api::{
+  compare_commits, ApiClient, Commit, CommitAuthor, CommitSource, Issue, PullRequestRef,
+};
+
+/// Resolves the PR a commit was merged with (the first one, if it was cherry-picked into others), the commits are aliased as `c0`, `c1`, ... in `commits_query`
+const PULL_REQUEST_FRAGMENT: &str = r#"
+fragment PullRequest on Commit {
+  associatedPullRequests(first: 1, states: MERGED, orderBy: { field: CREATED_AT, direction: ASC }) {
+    nodes {
+      number
+      labels(first: 20) {
+        nodes {
+          name
+        }
+      }
+    }
+  }
+}
+"#;
+
+/// Number of commits resolved with a single query
+const BATCH_SIZE: usize = 100;
+
+const CLOSING_ISSUES_QUERY: &str = r#"
+query($owner: String!, $repo: String!, $number: Int!) {
+  repository(owner: $owner, name: $repo) {
+    pullRequest(number: $number) {
+      closingIssuesReferences(first: 20) {
+        nodes {
+          number
+          title
+          author {
+            login
+            url
+          }
+        }
+      }
+    }
+  }
+}
+"#;
+
+/// Issues each PR closes, either by a keyword like "Fixes #1" or by linking them manually.
+/// There's no REST endpoint for these, so this needs a token even with the REST backend.
+pub async fn fetch_closing_issues(
+  owner: &str,
+  repo: &str,
+  numbers: Vec<String>,
+  api: &ApiClient,
+) -> Result<HashMap<String, Vec<Issue>>> {
+  let unique_numbers: HashSet<String> = numbers.into_iter().collect();
+
+  stream::iter(unique_numbers)
+    .map(|number| async move {
+      let pr_number: u64 = number
+        .parse()
+        .map_err(|_| NapiError::from_reason(format!("Invalid PR number '{number}'")))?;
+      let pull_request = api
+        .graphql::<PullRequestResponse>(
+          CLOSING_ISSUES_QUERY,
+          json!({ "owner": owner, "repo": repo, "number": pr_number }),
+        )
+        .await?
+        .repository
+        .pull_request;
+      let issues = pull_request
+        .map(|pr| pr.closing_issues_references.nodes)
+        .unwrap_or_default()
+        .into_iter()
+        .map(Issue::from)
+        .collect();
+
+      Ok((number, issues))
+    })
+    .buffer_unordered(8)
+    .try_collect()
+    .await
+}
+
+/// Query looking up `count` commits at once, each one by its own `$oid` variable
+fn commits_query(count: usize) -> String {
+  let variables: String = (0..count)
+    .map(|i| format!(", $oid{i}: GitObjectID!"))
+    .collect();
+  let commits: String = (0..count)
+    .map(|i| format!("    c{i}: object(oid: $oid{i}) {{ ...PullRequest }}\n"))
+    .collect();
+
+  format!(
+    "query($owner: String!, $repo: String!{variables}) {{\n  repository(owner: $owner, name: $repo) {{\n{commits}  }}\n}}\n{PULL_REQUEST_FRAGMENT}"
+  )
+}
+
+/// Fetches the commits of the compare endpoint and resolves their PRs and labels in batches of 100.
+/// GraphQL can't list the commits of `base...head` itself, its history only filters by date.
+pub struct GraphQlCommitSource<'a> {
+  api: &'a ApiClient,
+}
+
+impl<'a> GraphQlCommitSource<'a> {
+  pub fn new(api: &'a ApiClient) -> Self {
+    Self { api }
+  }
+}
+
+#[async_trait]
+impl CommitSource for GraphQlCommitSource<'_> {
+  async fn list_commits(
+    &self,
+    owner: &str,
+    repo: &str,
+    base: &str,
+    head: &str,
+  ) -> Result<Vec<Commit>> {
+    let mut commits =
+      compare_commits(owner, repo, base.to_owned(), head.to_owned(), self.api).await?;
+
+    for batch in commits.chunks_mut(BATCH_SIZE) {
+      let mut variables = json!({ "owner": owner, "repo": repo });
+      for (i, commit) in batch.iter().enumerate() {
+        variables[format!("oid{i}")] = json!(commit.sha);
+      }
+
+      let mut nodes = self
+        .api
+        .graphql::<CommitsResponse>(&commits_query(batch.len()), variables)
+        .await?
+        .repository;
+
+      for (i, commit) in batch.iter_mut().enumerate() {
+        if let Some(Some(node)) = nodes.remove(&format!("c{i}")) {
+          commit.pull_request = node.into_pull_request();
+        }
+      }
+    }
+
+    Ok(commits)
+  }
+}
+
+impl CommitNode {
+  fn into_pull_request(self) -> Option<PullRequestRef> {
+    self
+      .associated_pull_requests
+      .nodes
+      .into_iter()
+      .next()
+      .map(|pr| PullRequestRef {
+        number: pr.number,
+        labels: Some(pr.labels.nodes.into_iter().map(|l| l.name).collect()),
+      })
+  }
+}
+
+/// The aliased commits of `commits_query`, `None` for commits GitHub doesn't know
+#[derive(Debug, Deserialize)]
+pub struct CommitsResponse {
+  pub repository: HashMap<String, Option<CommitNode>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitNode {
+  pub associated_pull_requests: Nodes<PullRequestNode>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Actor {
+  pub login: String,
+  pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Nodes<T> {
+  pub nodes: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PullRequestNode {
+  pub number: u64,
+  pub labels: Nodes<Label>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Label {
+  pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PullRequestResponse {
+  pub repository: PullRequestRepository,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequestRepository {
+  pub pull_request: Option<ClosingIssues>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClosingIssues {
+  pub closing_issues_references: Nodes<IssueNode>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IssueNode {
+  pub number: u64,
+  pub title: String,
+  /// Missing for deleted accounts
+  pub author: Option<Actor>,
+}
+
+impl From<IssueNode> for Issue {
+  fn from(node: IssueNode) -> Self {
+    Issue {
+      number: node.number,
+      title: node.title,
+      author: node.author.map(|u| CommitAuthor {
+        login: u.login,
+        html_url: u.url,
+      }),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::forge::ForgeKind;
+  use crate::test_utils::stub_server;
+
+  #[test]
+  fn commits_query_aliases_each_commit() {
+    let query = commits_query(2);
+
+    assert!(query.starts_with(
+      "query($owner: String!, $repo: String!, $oid0: GitObjectID!, $oid1: GitObjectID!) {"
+    ));
+    assert!(query.contains("c1: object(oid: $oid1) { ...PullRequest }"));
+    assert!(query.contains("fragment PullRequest on Commit"));
+  }
+
+  #[tokio::test]
+  async fn fetch_closing_issues_issue_has_no_closing_issues() {
+    let url = stub_server(vec![(
+      "/api/v3/graphql",
+      String::new(),
+      r#"{
+        "data": { "repository": { "pullRequest": null } },
+        "errors": [{
+          "type": "NOT_FOUND",
+          "path": ["repository", "pullRequest"],
+          "message": "Could not resolve to a PullRequest with the number of 5."
+        }]
+      }"#
+        .to_string(),
+    )]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+
+    let issues = fetch_closing_issues("acme", "widgets", vec!["5".to_string()], &api)
+      .await
+      .unwrap();
+
+    assert!(issues["5"].is_empty());
+  }
+
+  #[tokio::test]
+  async fn graphql_fails_on_other_errors() {
+    let url = stub_server(vec![(
+      "/api/v3/graphql",
+      String::new(),
+      r#"{ "data": null, "errors": [{ "type": "FORBIDDEN", "message": "Resource not accessible" }] }"#
+        .to_string(),
+    )]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+
+    let result = fetch_closing_issues("acme", "widgets", vec!["5".to_string()], &api).await;
+
+    assert!(result.is_err());
+  }
+
+  #[tokio::test]
+  async fn list_commits_keeps_the_compared_range() {
+    let commit = |sha: &str, date: &str| {
+      format!(
+        r#"{{ "sha": "{sha}", "url": "", "commit": {{ "url": "", "message": "Commit {sha}", "author": {{ "name": "Harry", "email": "harry@example.com", "date": "{date}" }} }}, "author": null }}"#
+      )
+    };
+    // A side branch with commits older than v1.0.0 was merged after it, and nothing of v1.0.0 is listed
+    let compare = format!(
+      r#"{{ "commits": [{}, {}, {}] }}"#,
+      commit("side", "2023-12-01T00:00:00Z"),
+      commit("merge", "2024-01-15T00:00:00Z"),
+      commit("direct", "2024-01-20T00:00:00Z")
+    );
+    let url = stub_server(vec![
+      (
+        "/api/v3/repos/acme/widgets/compare/v1.0.0...v1.1.0?per_page=100&page=1",
+        String::new(),
+        compare,
+      ),
+      (
+        "/api/v3/graphql",
+        String::new(),
+        r#"{ "data": { "repository": {
+          "c0": { "associatedPullRequests": { "nodes": [{ "number": 3, "labels": { "nodes": [{ "name": "feature" }] } }] } },
+          "c1": { "associatedPullRequests": { "nodes": [{ "number": 3, "labels": { "nodes": [] } }] } },
+          "c2": { "associatedPullRequests": { "nodes": [] } }
+        } } }"#
+          .to_string(),
+      ),
+    ]);
+    let api = ApiClient::new("token").base_url(ForgeKind::GitHub.api_url(&url));
+
+    let commits = GraphQlCommitSource::new(&api)
+      .list_commits("acme", "widgets", "v1.0.0", "v1.1.0")
+      .await
+      .unwrap();
+
+    let shas: Vec<_> = commits.iter().map(|c| c.sha.as_str()).collect();
+    assert_eq!(shas, vec!["side", "merge", "direct"]);
+    assert_eq!(
+      commits[0].pull_request,
+      Some(PullRequestRef {
+        number: 3,
+        labels: Some(vec!["feature".to_string()]),
+      })
+    );
+    assert_eq!(commits[2].pull_request, None);
+  }
+}
@@ -0,0 +1,272 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::api::{Commit, CommitAuthor};
+
+pub const DEFAULT_MAILMAP_FILE: &str = ".mailmap";
+
+/// Maps the different names, emails and logins of a person to one canonical identity
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Identities {
+  /// Lowercased name, email or login mapped to the canonical identity
+  lookup: HashMap<String, String>,
+  /// Proper name of a canonical identity from the .mailmap
+  names: HashMap<String, String>,
+  /// Canonical identities that are GitHub logins (from the aliases in the config)
+  logins: HashMap<String, String>,
+}
+
+impl Identities {
+  /// Adds the entries of a .mailmap file. The canonical identity is the proper email.
+  pub fn add_mailmap(&mut self, content: &str) {
+    lazy_static! {
+      // Regex is not dynamic so .unwrap is fine
+      static ref RE: Regex = Regex::new(
+        r"^\s*(?P<proper_name>[^<]*?)\s*<(?P<proper_email>[^>]*)>\s*(?:(?P<commit_name>[^<]*?)\s*<(?P<commit_email>[^>]*)>)?\s*$"
+      )
+      .unwrap();
+    }
+
+    for line in content.lines() {
+      let line = line.split('#').next().unwrap_or_default();
+      let Some(caps) = RE.captures(line) else {
+        continue;
+      };
+
+      let non_empty = |name: &str| {
+        caps
+          .name(name)
+          .map(|m| m.as_str())
+          .filter(|m| !m.is_empty())
+      };
+
+      let Some(canonical) = non_empty("proper_email").map(str::to_lowercase) else {
+        continue;
+      };
+
+      if let Some(proper_name) = non_empty("proper_name") {
+        self
+          .names
+          .insert(canonical.clone(), proper_name.to_string());
+        self.insert(proper_name, &canonical);
+      }
+      if let Some(commit_name) = non_empty("commit_name") {
+        self.insert(commit_name, &canonical);
+      }
+      if let Some(commit_email) = non_empty("commit_email") {
+        self.insert(commit_email, &canonical);
+      }
+      self.insert(&canonical.clone(), &canonical);
+    }
+  }
+
+  /// Adds the aliases from the config. The canonical identity is the GitHub login.
+  pub fn add_aliases(&mut self, aliases: &HashMap<String, Vec<String>>) {
+    for (login, others) in aliases {
+      let canonical = login.to_lowercase();
+
+      self.logins.insert(canonical.clone(), login.clone());
+      self.insert(login, &canonical);
+      for other in others {
+        self.insert(other, &canonical);
+      }
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.lookup.is_empty()
+  }
+
+  fn insert(&mut self, key: &str, canonical: &str) {
+    self
+      .lookup
+      .insert(key.trim().to_lowercase(), canonical.to_string());
+  }
+
+  fn canonical(&self, commit: &Commit) -> Option<&String> {
+    let login = commit.author.as_ref().map(|a| a.login.as_str());
+
+    [
+      login,
+      Some(commit.commit.author.email.as_str()),
+      Some(commit.commit.author.name.as_str()),
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(|key| self.lookup.get(&key.to_lowercase()))
+  }
+}
+
+/// Rewrites the authors of the commits so that all commits of the same person end up in one group.
+/// If any commit of a person is linked to a GitHub account, all of their commits get that account.
+pub fn merge_identities(
+  commits: Vec<Commit>,
+  identities: &Identities,
+  user_url: impl Fn(&str) -> String,
+) -> Vec<Commit> {
+  if identities.is_empty() {
+    return commits;
+  }
+
+  let mut accounts: HashMap<String, CommitAuthor> = HashMap::new();
+  for (canonical, login) in &identities.logins {
+    accounts.insert(
+      canonical.clone(),
+      CommitAuthor {
+        login: login.clone(),
+        html_url: user_url(login),
+      },
+    );
+  }
+  for c in &commits {
+    if let (Some(canonical), Some(author)) = (identities.canonical(c), &c.author) {
+      accounts
+        .entry(canonical.clone())
+        .or_insert_with(|| author.clone());
+    }
+  }
+
+  commits
+    .into_iter()
+    .map(|mut c| {
+      let Some(canonical) = identities.canonical(&c).cloned() else {
+        return c;
+      };
+
+      if let Some(account) = accounts.get(&canonical) {
+        c.author = Some(account.clone());
+      } else if let Some(name) = identities.names.get(&canonical) {
+        c.commit.author.name = name.clone();
+      }
+
+      c
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::api::{CommitMeta, CommitMetaAuthor};
+
+  use super::*;
+
+  fn commit(name: &str, email: &str, login: Option<&str>) -> Commit {
+    Commit {
+      sha: "sha".to_string(),
+      url: "url".to_string(),
+      commit: CommitMeta {
+        url: "url".to_string(),
+        message: "fix(scope): Message (#1)".to_string(),
+        author: CommitMetaAuthor {
+          name: name.to_string(),
+          email: email.to_string(),
+        },
+      },
+      author: login.map(|l| CommitAuthor {
+        login: l.to_string(),
+        html_url: format!("{l}-url"),
+      }),
+      pull_request: None,
+    }
+  }
+
+  fn github_url(login: &str) -> String {
+    format!("https://github.com/{login}")
+  }
+
+  fn authors(commits: Vec<Commit>) -> Vec<(String, Option<String>)> {
+    commits
+      .into_iter()
+      .map(|c| (c.commit.author.name, c.author.map(|a| a.login)))
+      .collect()
+  }
+
+  #[test]
+  fn merge_identities_mailmap_with_login() {
+    let mut identities = Identities::default();
+    identities.add_mailmap(
+      "# Comment\nJohn Doe <john@example.com> <john@laptop.local>\nJohn Doe <john@example.com> Johnny <johnny@example.com>\n",
+    );
+
+    let commits = vec![
+      commit("John Doe", "john@example.com", Some("jdoe")),
+      commit("john", "john@laptop.local", None),
+      commit("Johnny", "johnny@example.com", None),
+      commit("Jane", "jane@example.com", None),
+    ];
+
+    assert_eq!(
+      authors(merge_identities(commits, &identities, github_url)),
+      vec![
+        ("John Doe".to_string(), Some("jdoe".to_string())),
+        ("john".to_string(), Some("jdoe".to_string())),
+        ("Johnny".to_string(), Some("jdoe".to_string())),
+        ("Jane".to_string(), None),
+      ]
+    )
+  }
+
+  #[test]
+  fn merge_identities_mailmap_without_login() {
+    let mut identities = Identities::default();
+    identities.add_mailmap("John Doe <john@example.com>\n<john@example.com> <john@laptop.local>");
+
+    let commits = vec![
+      commit("john", "john@example.com", None),
+      commit("jd", "john@laptop.local", None),
+    ];
+
+    assert_eq!(
+      authors(merge_identities(commits, &identities, github_url)),
+      vec![
+        ("John Doe".to_string(), None),
+        ("John Doe".to_string(), None),
+      ]
+    )
+  }
+
+  #[test]
+  fn merge_identities_aliases() {
+    let mut identities = Identities::default();
+    identities.add_aliases(&HashMap::from([(
+      "jdoe".to_string(),
+      vec!["John Doe".to_string(), "jdoe-old".to_string()],
+    )]));
+
+    let commits = vec![
+      commit("John Doe", "john@example.com", None),
+      commit("John", "john@example.com", Some("jdoe-old")),
+      commit("Jane", "jane@example.com", None),
+    ];
+
+    let merged = merge_identities(commits, &identities, github_url);
+
+    assert_eq!(
+      merged[0].author.as_ref().map(|a| a.html_url.as_str()),
+      Some("https://github.com/jdoe")
+    );
+    assert_eq!(
+      authors(merged),
+      vec![
+        ("John Doe".to_string(), Some("jdoe".to_string())),
+        ("John".to_string(), Some("jdoe".to_string())),
+        ("Jane".to_string(), None),
+      ]
+    )
+  }
+
+  #[test]
+  fn merge_identities_empty() {
+    let commits = vec![commit("John Doe", "john@example.com", None)];
+
+    assert_eq!(
+      authors(merge_identities(
+        commits,
+        &Identities::default(),
+        github_url
+      )),
+      vec![("John Doe".to_string(), None)]
+    )
+  }
+}
@@ -1,19 +1,55 @@
 #[macro_use]
 extern crate napi_derive;
 
-use crate::api::{compare_commits, list_members};
-use crate::error::{env_var_error, format_cli_error};
-use crate::utils::{create_entries, create_output, get_current_date, group_by_author};
-use clap::{CommandFactory, FromArgMatches, Parser};
+use crate::api::{
+  fetch_pr_labels, fetch_reviewers, fetch_users, find_first_time_contributors, get_commit_date,
+  ApiClient, CollaboratorPermission, CommitSource, GitHubForge, RestCommitSource,
+};
+use crate::auth::github_token;
+use crate::bitbucket::{BitbucketCloudForge, BitbucketServerForge};
+use crate::cache::{default_cache_dir, Cache};
+use crate::config::{load_config, DEFAULT_CONFIG_FILE};
+use crate::error::{env_var_error, file_error, format_cli_error};
+use crate::forge::{list_members_of_orgs, Forge, ForgeKind};
+use crate::gitea::GiteaForge;
+use crate::gitlab::GitLabForge;
+use crate::graphql::{fetch_closing_issues, GraphQlCommitSource};
+use crate::identity::{merge_identities, Identities, DEFAULT_MAILMAP_FILE};
+use crate::utils::{
+  create_avatar_output, create_entries, create_first_time_output, create_output,
+  create_report_entries, create_review_entries, create_section_output, create_stats,
+  create_stats_output, dedupe_entries, filter_labels, filter_min_contributions, filter_only,
+  get_current_date, group_and_sort, group_by_author, is_excluded, parse_date, parse_logins,
+  with_display_names, with_label_tags, Entry, SortOrder,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, ValueEnum};
 use clap_verbosity_flag::Verbosity;
 use dotenv::dotenv;
-use log::{debug, info};
+use futures::future::try_join_all;
+use log::{debug, info, warn};
 use napi::bindgen_prelude::{Error as NapiError, Result};
+use reqwest::header::{HeaderName, AUTHORIZATION};
+use std::collections::HashMap;
 use std::env;
 use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
 
 pub mod api;
+pub mod auth;
+pub mod bitbucket;
+pub mod cache;
+pub mod config;
 pub mod error;
+pub mod forge;
+pub mod gitea;
+pub mod gitlab;
+pub mod graphql;
+pub mod identity;
+#[cfg(test)]
+mod test_utils;
 pub mod utils;
 
 #[allow(dead_code)]
@@ -24,7 +60,22 @@ async fn run(args: Vec<String>) -> Result<()> {
 
   // Arguments are coming from bin.js
   let matches = Cli::command().get_matches_from(args);
-  let cli = Cli::from_arg_matches(&matches).map_err(format_cli_error::<Cli>)?;
+  let mut cli = Cli::from_arg_matches(&matches).map_err(format_cli_error::<Cli>)?;
+
+  // With a date range, BASE and HEAD aren't needed, so the positional arguments are OWNER and REPO
+  if cli.since.is_some() {
+    if matches.value_source("owner") == Some(ValueSource::CommandLine) {
+      return Err(NapiError::from_reason(
+        "BASE and HEAD can't be combined with --since, pass only [OWNER] [REPO]".to_owned(),
+      ));
+    }
+    if let Some(owner) = cli.base.take() {
+      cli.owner = owner;
+    }
+    if let Some(repo) = cli.head.take() {
+      cli.repo = repo;
+    }
+  }
 
   env_logger::Builder::new()
     .filter_level(cli.verbose.log_level_filter())
@@ -32,6 +83,20 @@ async fn run(args: Vec<String>) -> Result<()> {
 
   // By default, don't include org members
   let should_include_org_members = cli.include_org_members.unwrap_or(false);
+  // By default, keep backport references in the message
+  let should_strip_backport_refs = cli.strip_backport_refs.unwrap_or(false);
+  // By default, keep commits without a PR number and link to the commit instead
+  let should_require_pr = cli.require_pr.unwrap_or(false);
+  // By default, don't look up first-time contributors as it needs one request per author
+  let should_highlight_first_time = cli.highlight_first_time.unwrap_or(false);
+  // By default, only print the list itself
+  let should_show_stats = cli.stats.unwrap_or(false);
+  // By default, only show logins and don't fetch any user details
+  let should_show_display_names = cli.display_names.unwrap_or(false);
+  let should_show_avatars = cli.avatars.unwrap_or(false);
+  let credits = cli.credit.clone().unwrap_or_default();
+  let should_credit_reviewers = credits.contains(&Credit::Reviewers);
+  let should_credit_reporters = credits.contains(&Credit::Reporters);
   // By default, exclude renovate bot
   let parsed_excludes = match cli.excludes {
     Some(e) => e,
@@ -40,10 +105,250 @@ async fn run(args: Vec<String>) -> Result<()> {
 
   debug!("Parsed Excludes: {:#?}", parsed_excludes);
 
-  let gh_token = env::var("GITHUB_ACCESS_TOKEN").map_err(env_var_error)?;
+  let label_tags = cli
+    .tag_label
+    .iter()
+    .flatten()
+    .map(|value| {
+      value
+        .split_once('=')
+        .map(|(label, tag)| (label.to_owned(), tag.to_owned()))
+        .ok_or_else(|| {
+          NapiError::from_reason(format!("Invalid --tag-label '{value}', expected LABEL=TAG"))
+        })
+    })
+    .collect::<Result<Vec<_>>>()?;
+
+  // Logins prefixed with "@" are files listing them
+  let only = match &cli.only {
+    Some(values) => {
+      let mut logins = Vec::new();
+      for value in values {
+        match value.strip_prefix('@') {
+          Some(path) => {
+            let contents = fs::read_to_string(path).map_err(|e| file_error(path.as_ref(), e))?;
+            logins.extend(parse_logins(&contents));
+          }
+          None => logins.push(value.clone()),
+        }
+      }
+      Some(logins)
+    }
+    None => None,
+  };
+
+  let config = load_config(
+    cli
+      .config
+      .as_deref()
+      .unwrap_or(DEFAULT_CONFIG_FILE.as_ref()),
+    cli.config.is_some(),
+  )?;
+
+  let mut identities = Identities::default();
+  let mailmap_path = cli
+    .mailmap
+    .clone()
+    .unwrap_or_else(|| PathBuf::from(DEFAULT_MAILMAP_FILE));
+  // The default .mailmap is optional, an explicitly passed one is not
+  if cli.mailmap.is_some() || mailmap_path.exists() {
+    let mailmap = fs::read_to_string(&mailmap_path).map_err(|e| file_error(&mailmap_path, e))?;
+    identities.add_mailmap(&mailmap);
+  }
+  identities.add_aliases(&config.aliases);
+
+  debug!("Identities: {:#?}", identities);
+
+  // Clap requires BASE and HEAD unless there's a date range
+  let base = cli.base.as_deref().unwrap_or_default();
+  let head = cli.head.as_deref().unwrap_or_default();
+  let date_range = match cli.since {
+    Some(since) => {
+      // Resolved once, so the check and the requested range use the same time
+      let until = cli.until.unwrap_or_else(Utc::now);
+      let (since_str, until_str) = (
+        since.to_rfc3339_opts(SecondsFormat::Secs, true),
+        until.to_rfc3339_opts(SecondsFormat::Secs, true),
+      );
+      if since > until {
+        return Err(NapiError::from_reason(format!(
+          "--since ({since_str}) is later than --until ({until_str})"
+        )));
+      }
+      Some((since_str, until_str))
+    }
+    None => None,
+  };
 
-  let commits = compare_commits(&cli.owner, &cli.repo, cli.base, cli.head, &gh_token).await?;
-  let org_members = list_members(&cli.owner, &gh_token).await?;
+  if date_range.is_some() && should_highlight_first_time {
+    return Err(NapiError::from_reason(
+      "--highlight-first-time needs BASE and can't be combined with --since".to_owned(),
+    ));
+  }
+
+  if date_range.is_some() && cli.backend == Backend::Graphql {
+    return Err(NapiError::from_reason(
+      "--backend graphql needs BASE and HEAD and can't be combined with --since".to_owned(),
+    ));
+  }
+
+  let forge_url = cli
+    .forge_url
+    .clone()
+    .or_else(|| cli.forge.default_url().map(str::to_owned))
+    .ok_or_else(|| {
+      NapiError::from_reason("--forge-url is required for Bitbucket Server".to_owned())
+    })?;
+  let token_var = match cli.forge {
+    ForgeKind::GitHub => "GITHUB_ACCESS_TOKEN",
+    ForgeKind::GitLab => "GITLAB_TOKEN",
+    ForgeKind::Gitea => "GITEA_TOKEN",
+    ForgeKind::Bitbucket | ForgeKind::BitbucketServer => "BITBUCKET_TOKEN",
+  };
+  let token = match (&cli.replay, cli.forge) {
+    // Replaying doesn't send any requests, so no token is needed
+    (Some(_), _) => env::var(token_var).unwrap_or_default(),
+    (None, ForgeKind::GitHub) => {
+      let token = github_token(
+        &cli.forge.api_url(&forge_url),
+        &forge_url,
+        &cli.owner,
+        &cli.repo,
+      )
+      .await?;
+      match token {
+        Some((token, source)) => {
+          info!("Using the GitHub token from {source}");
+          token
+        }
+        None => {
+          // Printed even without -v, as it changes the result
+          eprintln!(
+            "Warning: No GitHub token found, continuing without authentication. This is limited to 60 requests per hour and only excludes the public members of the organization. Set GITHUB_ACCESS_TOKEN or log in with `gh auth login` to avoid this."
+          );
+          String::new()
+        }
+      }
+    }
+    (None, _) => env::var(token_var).map_err(|e| env_var_error(token_var, e))?,
+  };
+  let is_anonymous = cli.replay.is_none() && token.is_empty();
+
+  if is_anonymous && (cli.backend == Backend::Graphql || should_credit_reporters) {
+    return Err(NapiError::from_reason(
+      "--backend graphql and --credit reporters require a GitHub token".to_owned(),
+    ));
+  }
+
+  if cli.forge != ForgeKind::GitHub
+    && (should_highlight_first_time
+      || should_show_display_names
+      || should_show_avatars
+      || cli.exclude_team.is_some()
+      || cli.exclude_collaborators.is_some()
+      || cli.include_label.is_some()
+      || cli.exclude_label.is_some()
+      || cli.tag_label.is_some()
+      || cli.credit.is_some()
+      || cli.backend != Backend::Rest)
+  {
+    return Err(NapiError::from_reason(
+      "--highlight-first-time, --display-names, --avatars, --exclude-team, --exclude-collaborators, --credit, the label options and --backend graphql are only supported on GitHub"
+        .to_owned(),
+    ));
+  }
+
+  let api = ApiClient::new(&token).base_url(cli.forge.api_url(&forge_url));
+  let api = match cli.forge {
+    ForgeKind::GitHub if is_anonymous => api.anonymous(),
+    ForgeKind::GitHub | ForgeKind::Gitea => api,
+    ForgeKind::GitLab => api.auth_header(HeaderName::from_static("private-token"), token),
+    ForgeKind::Bitbucket | ForgeKind::BitbucketServer => {
+      api.auth_header(AUTHORIZATION, format!("Bearer {token}"))
+    }
+  };
+  let api = match cli.replay {
+    Some(dir) => api.replay(dir),
+    None => api,
+  };
+  let api = match cli.record {
+    Some(dir) => api.record(dir),
+    None => api,
+  };
+  // By default, cache responses and revalidate them on the next run
+  let cache_dir = if cli.no_cache {
+    None
+  } else {
+    cli.cache_dir.or_else(default_cache_dir)
+  };
+  let api = match cache_dir {
+    Some(dir) => {
+      debug!("Caching responses in {}", dir.display());
+      api.cache(Cache::new(dir, cli.cache_ttl.map(Duration::from_secs)))
+    }
+    None => api,
+  };
+
+  let forge: Box<dyn Forge + '_> = match cli.forge {
+    ForgeKind::GitHub => {
+      let commit_source: Box<dyn CommitSource + '_> = match cli.backend {
+        Backend::Rest => Box::new(RestCommitSource::new(&api)),
+        Backend::Graphql => Box::new(GraphQlCommitSource::new(&api)),
+      };
+      Box::new(GitHubForge::new(&api, commit_source, &forge_url))
+    }
+    ForgeKind::GitLab => Box::new(GitLabForge::new(&api, &forge_url)),
+    ForgeKind::Gitea => Box::new(GiteaForge::new(&api, &forge_url)),
+    ForgeKind::Bitbucket => Box::new(BitbucketCloudForge::new(&api, &forge_url)),
+    ForgeKind::BitbucketServer => Box::new(BitbucketServerForge::new(&api, &forge_url)),
+  };
+
+  let commits = match &date_range {
+    Some((since, until)) => {
+      forge
+        .list_commits_between(&cli.owner, &cli.repo, cli.branch.as_deref(), since, until)
+        .await?
+    }
+    None => {
+      forge
+        .list_commits(&cli.owner, &cli.repo, base, head)
+        .await?
+    }
+  };
+  // A known list of members, the members of some teams and/or the collaborators replace all members of the organization
+  // Everyone on the allowlist is thanked, so the members aren't needed
+  let org_members = if only.is_some() {
+    Vec::new()
+  } else if cli.members_file.is_some()
+    || cli.exclude_team.is_some()
+    || cli.exclude_collaborators.is_some()
+  {
+    let mut members = match &cli.members_file {
+      Some(path) => parse_logins(&fs::read_to_string(path).map_err(|e| file_error(path, e))?),
+      None => Vec::new(),
+    };
+    let teams = cli.exclude_team.as_deref().unwrap_or_default();
+    let team_members = try_join_all(
+      teams
+        .iter()
+        .map(|team| forge.list_team_members(&cli.owner, team)),
+    )
+    .await?;
+    members.extend(team_members.into_iter().flatten());
+    if let Some(permission) = cli.exclude_collaborators {
+      let collaborators = forge
+        .list_collaborators(&cli.owner, &cli.repo, permission)
+        .await?;
+      members.extend(collaborators);
+    }
+    members
+  } else {
+    let orgs = cli
+      .member_orgs
+      .clone()
+      .unwrap_or_else(|| vec![cli.owner.clone()]);
+    list_members_of_orgs(forge.as_ref(), &orgs, &cli.owner, &cli.repo).await
+  };
 
   debug!("Commits: {:#?}", commits);
   debug!("Org members: {:#?}", org_members);
@@ -57,17 +362,277 @@ async fn run(args: Vec<String>) -> Result<()> {
 
   info!("Fetched {} commits", commits.len());
 
-  let entries = create_entries(
-    commits,
-    should_include_org_members,
-    parsed_excludes,
-    org_members,
-  );
+  let total_commits = commits.len();
+  let commits = merge_identities(commits, &identities, |login| forge.user_url(login));
+
+  let is_excluded_person = |entry: &Entry| match &only {
+    // Members don't matter, but the excludes still apply
+    Some(_) => is_excluded(entry, false, &parsed_excludes, &[]),
+    None => is_excluded(
+      entry,
+      should_include_org_members,
+      &parsed_excludes,
+      &org_members,
+    ),
+  };
+
+  let should_credit = should_credit_reviewers || should_credit_reporters;
+  let should_filter_labels =
+    cli.include_label.is_some() || cli.exclude_label.is_some() || !label_tags.is_empty();
+
+  // Everyone's entries, the PRs of excluded authors can still be reviewed by or fix issues of others
+  let all_entries = create_entries(commits);
+  let all_entries = if should_filter_labels {
+    // The GraphQL backend already resolved the labels of the PRs it found.
+    // The PRs of excluded authors only matter for the reviewers and reporters.
+    let numbers = all_entries
+      .iter()
+      .filter(|e| e.labels.is_none() && (should_credit || !is_excluded_person(e)))
+      .filter_map(|e| e.pr_number.clone())
+      .collect();
+    let labels = fetch_pr_labels(&cli.owner, &cli.repo, numbers, &api).await?;
+
+    all_entries
+      .into_iter()
+      .map(|mut e| {
+        if let Some(pr_labels) = e.pr_number.as_ref().and_then(|n| labels.get(n)) {
+          e.labels = Some(pr_labels.clone());
+        }
+        e
+      })
+      .collect()
+  } else {
+    all_entries
+  };
+  let include_labels = cli.include_label.as_deref().unwrap_or_default();
+  let exclude_labels = cli.exclude_label.as_deref().unwrap_or_default();
+
+  let credited_entries = if should_credit {
+    filter_labels(
+      dedupe_entries(all_entries.clone(), should_strip_backport_refs),
+      include_labels,
+      exclude_labels,
+    )
+  } else {
+    Vec::new()
+  };
+
+  let entries: Vec<Entry> = all_entries
+    .into_iter()
+    .filter(|e| !is_excluded_person(e))
+    .collect();
+  let entries = match &only {
+    Some(only) => {
+      let (entries, missing) = filter_only(entries, only);
+
+      if !missing.is_empty() {
+        // stdout only has the path of the created file, and warnings need -v
+        eprintln!(
+          "Warning: {} of the listed people have no commits in this range: {}",
+          missing.len(),
+          missing.join(", ")
+        );
+      }
+
+      entries
+    }
+    None => entries,
+  };
+
+  // Based on the commits, before any deduplication or threshold drops some of them
+  let stats = create_stats(total_commits, &entries, 5);
+
+  debug!("Stats: {:#?}", stats);
+
+  let entries = dedupe_entries(entries, should_strip_backport_refs);
+  let entries = filter_labels(entries, include_labels, exclude_labels);
+
+  let entries = if should_require_pr {
+    let (entries, dropped): (Vec<_>, Vec<_>) =
+      entries.into_iter().partition(|e| e.pr_number.is_some());
+
+    if !dropped.is_empty() {
+      let list = dropped
+        .iter()
+        .map(|e| format!("- {}: {}", e.author, e.message.as_deref().unwrap_or("")))
+        .collect::<Vec<_>>()
+        .join("\n");
+      // Printed even without -v, so dropped contributions don't go unnoticed
+      eprintln!(
+        "Warning: Dropped {} commits without a PR number:\n{}",
+        dropped.len(),
+        list
+      );
+    }
+
+    entries
+  } else {
+    entries
+  };
 
   info!("Process {} filtered commits", entries.len());
 
-  let groups = group_by_author(entries);
-  let output = create_output(groups, &cli.owner, &cli.repo);
+  let entries = match cli.min_contributions {
+    Some(min) => filter_min_contributions(entries, min as usize),
+    None => entries,
+  };
+
+  let entries = with_label_tags(entries, &label_tags);
+
+  let sort_groups = |entries: Vec<Entry>| -> Vec<(String, Vec<Entry>)> {
+    match cli.sort {
+      Some(sort) => group_and_sort(entries, sort),
+      None => group_by_author(entries).into_iter().collect(),
+    }
+  };
+  // Reviewers and reporters are filtered like the authors
+  let is_credited = |entry: &Entry| {
+    let is_listed = only
+      .as_ref()
+      .is_none_or(|only| only.iter().any(|o| o.eq_ignore_ascii_case(&entry.author)));
+
+    is_listed && !is_excluded_person(entry)
+  };
+  let numbers: Vec<String> = credited_entries
+    .iter()
+    .filter_map(|e| e.pr_number.clone())
+    .collect();
+
+  let review_groups = if should_credit_reviewers {
+    let reviewers = fetch_reviewers(&cli.owner, &cli.repo, numbers.clone(), &api).await?;
+    let review_entries = create_review_entries(&credited_entries, &reviewers)
+      .into_iter()
+      .filter(is_credited)
+      .collect();
+    sort_groups(review_entries)
+  } else {
+    Vec::new()
+  };
+
+  let report_groups = if should_credit_reporters {
+    let issues = fetch_closing_issues(&cli.owner, &cli.repo, numbers, &api).await?;
+    let report_entries = create_report_entries(&credited_entries, &issues)
+      .into_iter()
+      .filter(is_credited)
+      .collect();
+    sort_groups(report_entries)
+  } else {
+    Vec::new()
+  };
+
+  let groups = sort_groups(entries);
+
+  let first_time_output = if should_highlight_first_time {
+    // Only authors with a GitHub account can be looked up
+    let logins = groups
+      .iter()
+      .filter(|(_, e)| e[0].author_url.is_some())
+      .map(|(author, _)| author.clone())
+      .collect();
+    let first_time_contributors =
+      find_first_time_contributors(&cli.owner, &cli.repo, base, logins, &api).await?;
+
+    info!(
+      "Found {} first-time contributors",
+      first_time_contributors.len()
+    );
+
+    create_first_time_output(&groups, &first_time_contributors)
+  } else {
+    String::new()
+  };
+
+  let users = if should_show_display_names || should_show_avatars {
+    // Only authors with a GitHub account have a profile
+    let logins = [&groups, &review_groups, &report_groups]
+      .into_iter()
+      .flatten()
+      .filter(|(_, e)| e[0].author_url.is_some())
+      .map(|(_, e)| e[0].author.clone())
+      .collect();
+    let users = fetch_users(logins, &api).await;
+
+    info!("Fetched details of {} users", users.len());
+
+    users
+  } else {
+    HashMap::new()
+  };
+
+  let avatar_output = if should_show_avatars {
+    create_avatar_output(&groups, &users, 64)
+  } else {
+    String::new()
+  };
+
+  let (groups, review_groups, report_groups) = if should_show_display_names {
+    (
+      with_display_names(groups, &users),
+      with_display_names(review_groups, &users),
+      with_display_names(report_groups, &users),
+    )
+  } else {
+    (groups, review_groups, report_groups)
+  };
+
+  let max_entries_per_author = cli.max_entries_per_author.map(|max| max as usize);
+  let is_collapsed = max_entries_per_author.is_some_and(|max| {
+    [&groups, &review_groups, &report_groups]
+      .into_iter()
+      .flatten()
+      .any(|(_, entries)| entries.len() > max)
+  });
+  let repo_urls = forge.repo_urls(&cli.owner, &cli.repo);
+  // Link the collapsed entries to the PRs merged in this range
+  let repo_urls = if is_collapsed && cli.forge == ForgeKind::GitHub {
+    let range = match &date_range {
+      Some(range) => Ok(range.clone()),
+      None => futures::try_join!(
+        get_commit_date(&cli.owner, &cli.repo, base, &api),
+        get_commit_date(&cli.owner, &cli.repo, head, &api)
+      ),
+    };
+    match range {
+      Ok((since, until)) => repo_urls.merged_between(
+        since.get(..10).unwrap_or(&since),
+        until.get(..10).unwrap_or(&until),
+      ),
+      Err(e) => {
+        warn!(
+          "Failed to get the dates of BASE and HEAD, linking to all merged PRs of the collapsed authors instead: {}",
+          e.reason
+        );
+        repo_urls
+      }
+    }
+  } else {
+    repo_urls
+  };
+
+  let mut output = String::new();
+  if should_show_stats {
+    output.push_str(&create_stats_output(&stats));
+  }
+  output.push_str(&create_output(groups, &repo_urls, max_entries_per_author));
+  output.push_str(&create_section_output(
+    "👀 Reviews",
+    &create_output(
+      review_groups,
+      &repo_urls.clone().search_by(Some("reviewed-by")),
+      max_entries_per_author,
+    ),
+  ));
+  output.push_str(&create_section_output(
+    "🐛 Bug reports",
+    // There's no search for the PRs closing someone's issues
+    &create_output(
+      report_groups,
+      &repo_urls.clone().search_by(None),
+      max_entries_per_author,
+    ),
+  ));
+  output.push_str(&first_time_output);
+  output.push_str(&avatar_output);
 
   let current_dir = env::current_dir()?;
   let directory_path = current_dir.join("output");
@@ -90,13 +655,13 @@ async fn run(args: Vec<String>) -> Result<()> {
 )]
 #[clap(no_binary_name = true)]
 struct Cli {
-  /// Pointer from where to start looking for changes
-  #[clap(required = true)]
-  base: String,
+  /// Pointer from where to start looking for changes. With --since, the positional arguments are [OWNER] [REPO] instead
+  #[clap(required_unless_present = "since")]
+  base: Option<String>,
   /// Pointer until where to stop looking for changes
-  #[clap(required = true)]
-  head: String,
-  /// Name of the owner/user/organization of the repository
+  #[clap(required_unless_present = "since")]
+  head: Option<String>,
+  /// Name of the owner/user/organization (or GitLab group, Bitbucket workspace/project) of the repository
   #[clap(default_value = "gatsbyjs")]
   owner: String,
   /// Name of the repository
@@ -114,10 +679,132 @@ struct Cli {
     value_delimiter = ',',
   )]
   excludes: Option<Vec<String>>,
+  /// Only list these people, e.g. the participants of a program. Usage: --only=user1,user2 or --only=@file with one login per line
+  #[clap(long, num_args = 1.., use_value_delimiter = true, value_delimiter = ',')]
+  only: Option<Vec<String>>,
+  /// Drop authors with fewer contributions than this
+  #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+  min_contributions: Option<u64>,
+  /// Collapse the entries of an author after this many into a "…and N more PRs" link
+  #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+  max_entries_per_author: Option<u64>,
+  /// Only list PRs with at least one of these labels. Usage: --include-label=feature,bug
+  #[clap(long, num_args = 1.., use_value_delimiter = true, value_delimiter = ',')]
+  include_label: Option<Vec<String>>,
+  /// Drop PRs with one of these labels. Usage: --exclude-label=skip-changelog
+  #[clap(long, num_args = 1.., use_value_delimiter = true, value_delimiter = ',')]
+  exclude_label: Option<Vec<String>>,
+  /// Tag the entries of PRs with a label. Usage: --tag-label="documentation=📝 docs,good first issue=🌱"
+  #[clap(long, num_args = 1.., use_value_delimiter = true, value_delimiter = ',')]
+  tag_label: Option<Vec<String>>,
+  /// Also credit the people who reviewed the PRs and/or reported the issues they closed. This includes the PRs of excluded authors, but not the ones dropped by --include-label/--exclude-label. Usage: --credit=reviewers,reporters
+  #[clap(long, value_enum, num_args = 1.., use_value_delimiter = true, value_delimiter = ',')]
+  credit: Option<Vec<Credit>>,
+  /// Path to a file with the logins of the organization members (one per line) to use instead of fetching them
+  #[clap(long)]
+  members_file: Option<PathBuf>,
+  /// Only exclude the members of these teams (by slug) instead of all organization members. Usage: --exclude-team=core,staff
+  #[clap(long, num_args = 1.., use_value_delimiter = true, value_delimiter = ',')]
+  exclude_team: Option<Vec<String>>,
+  /// Exclude the members of these organizations instead of the owner's, e.g. for jointly maintained or user-owned repositories. Usage: --member-orgs=org1,org2
+  #[clap(
+    long,
+    num_args = 1..,
+    use_value_delimiter = true,
+    value_delimiter = ',',
+    conflicts_with_all = ["members_file", "exclude_team", "exclude_collaborators"]
+  )]
+  member_orgs: Option<Vec<String>>,
+  /// Exclude the repository collaborators with at least this permission instead of the organization members. Usage: --exclude-collaborators=maintain [default if passed without a value: push]
+  // `require_equals`, as an optional value would otherwise swallow the positional argument after the flag
+  #[clap(
+    long,
+    value_enum,
+    num_args = 0..=1,
+    require_equals = true,
+    default_missing_value = "push"
+  )]
+  exclude_collaborators: Option<CollaboratorPermission>,
+  /// Strip the nested "(#123)" reference of backports from the message and mark them as "backport of #123" instead [default: false]
+  #[clap(long)]
+  strip_backport_refs: Option<bool>,
+  /// Drop commits without a PR number instead of linking to the commit [default: false]
+  #[clap(long)]
+  require_pr: Option<bool>,
+  /// Add a section listing contributors without any commit before BASE [default: false]
+  #[clap(long)]
+  highlight_first_time: Option<bool>,
+  /// Add a summary with the number of commits, contributors and PRs and the top contributors [default: false]
+  #[clap(long)]
+  stats: Option<bool>,
+  /// Sort order of the authors and their entries [default: authors by login, entries by commit order]
+  #[clap(long, value_enum)]
+  sort: Option<SortOrder>,
+  /// Fetch the profile of each contributor and render "Name (@login)" [default: false]
+  #[clap(long)]
+  display_names: Option<bool>,
+  /// Fetch the profile of each contributor and add a grid of their avatars as HTML [default: false]
+  #[clap(long)]
+  avatars: Option<bool>,
+  /// Path to a .mailmap file used to merge the different names and emails of a person [default: .mailmap]
+  #[clap(long)]
+  mailmap: Option<PathBuf>,
+  /// Path to a JSON config file, e.g. with "aliases" mapping a login to other names, emails and logins [default: thanks-contributors.json]
+  #[clap(long)]
+  config: Option<PathBuf>,
+  /// List the commits since this date instead of between BASE and HEAD, e.g. 2024-01-01, 2024-01-01T12:00:00Z or 30d (h, d and w are supported)
+  #[clap(long, value_parser = parse_date)]
+  since: Option<DateTime<Utc>>,
+  /// End of the date range [default: now]
+  #[clap(long, value_parser = parse_date, requires = "since")]
+  until: Option<DateTime<Utc>>,
+  /// Branch to list the commits of in the date range [default: the default branch]
+  #[clap(long, requires = "since")]
+  branch: Option<String>,
+  /// Save every response of GitHub's API as a fixture into the given directory
+  #[clap(long)]
+  record: Option<PathBuf>,
+  /// Serve the responses from fixtures saved with --record instead of GitHub's API
+  #[clap(long, conflicts_with = "record")]
+  replay: Option<PathBuf>,
+  /// Directory to cache the responses of GitHub's API in [default: $XDG_CACHE_HOME/thanks-contributors]
+  #[clap(long)]
+  cache_dir: Option<PathBuf>,
+  /// Use cached responses younger than the given seconds without revalidating them [default: always revalidate]
+  #[clap(long)]
+  cache_ttl: Option<u64>,
+  /// Don't read or write the cache
+  #[clap(long, conflicts_with_all = ["cache_dir", "cache_ttl"])]
+  no_cache: bool,
+  /// API used to fetch the commits
+  #[clap(long, value_enum, default_value_t = Backend::Rest)]
+  backend: Backend,
+  /// Platform the repository is hosted on
+  #[clap(long, value_enum, default_value_t = ForgeKind::GitHub)]
+  forge: ForgeKind,
+  /// URL of a self-hosted instance, e.g. https://gitlab.example.com [default: URL of the public instance]
+  #[clap(long)]
+  forge_url: Option<String>,
   #[clap(flatten)]
   verbose: Verbosity,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Credit {
+  /// Everyone who reviewed the PRs
+  Reviewers,
+  /// The authors of the issues closed by the PRs
+  Reporters,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Backend {
+  /// Compare endpoint of the REST API
+  Rest,
+  /// GraphQL API, also resolving the PR of each commit
+  Graphql,
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -127,4 +814,24 @@ mod tests {
     use clap::CommandFactory;
     Cli::command().debug_assert()
   }
+
+  #[test]
+  fn exclude_collaborators_keeps_the_following_argument() {
+    let cli =
+      Cli::try_parse_from(["v1", "v2", "--exclude-collaborators", "acme", "widgets"]).unwrap();
+
+    assert_eq!(
+      cli.exclude_collaborators,
+      Some(CollaboratorPermission::Push)
+    );
+    assert_eq!(cli.owner, "acme");
+    assert_eq!(cli.repo, "widgets");
+
+    let cli = Cli::try_parse_from(["v1", "v2", "--exclude-collaborators=maintain"]).unwrap();
+
+    assert_eq!(
+      cli.exclude_collaborators,
+      Some(CollaboratorPermission::Maintain)
+    );
+  }
 }
@@ -0,0 +1,54 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+/// Minimal HTTP server answering each request with the response of the first matching path, or a 404.
+/// `{url}` in a body is replaced with the URL of the server, e.g. for links to the next page.
+pub fn stub_server(routes: Vec<(&'static str, String, String)>) -> String {
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let url = format!("http://{}", listener.local_addr().unwrap());
+  let server_url = url.clone();
+
+  thread::spawn(move || {
+    for stream in listener.incoming() {
+      let mut stream = stream.unwrap();
+      let mut buffer = [0; 4096];
+      let read = stream.read(&mut buffer).unwrap();
+      let request = String::from_utf8_lossy(&buffer[..read]);
+      let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+      let (status, headers, body) = routes
+        .iter()
+        .find(|(p, _, _)| *p == path)
+        .map(|(_, headers, body)| {
+          (
+            "200 OK",
+            headers.clone(),
+            body.replace("{url}", &server_url),
+          )
+        })
+        .unwrap_or_else(|| {
+          (
+            "404 Not Found",
+            String::new(),
+            r#"{"message":"Not Found"}"#.to_string(),
+          )
+        });
+
+      let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{headers}\r\n{body}",
+        body.len()
+      );
+      stream.write_all(response.as_bytes()).unwrap();
+    }
+  });
+
+  url
+}
+
+/// JSON array of one `item` per index of `range`, e.g. for a page of members
+pub fn json_array(range: std::ops::Range<usize>, item: impl Fn(usize) -> String) -> String {
+  let items: Vec<String> = range.map(item).collect();
+
+  format!("[{}]", items.join(","))
+}
@@ -1,18 +1,56 @@
 use chrono::{
   format::{DelayedFormat, StrftimeItems},
-  DateTime, Utc,
+  DateTime, Duration, NaiveDate, NaiveTime, Utc,
 };
+use clap::ValueEnum;
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use crate::api::Commit;
+use crate::api::{Commit, CommitAuthor, Issue, User};
+use crate::forge::RepoUrls;
 
 pub fn get_current_date<'a>() -> DelayedFormat<StrftimeItems<'a>> {
   let now: DateTime<Utc> = Utc::now();
   now.format("%Y-%m-%d_%H-%M-%S")
 }
 
+/// Parses `--since`/`--until`: an RFC 3339 timestamp, a date (midnight UTC) or a relative value
+/// like `30d`, `2w` or `12h` before now
+pub fn parse_date(value: &str) -> Result<DateTime<Utc>, String> {
+  parse_date_at(value, Utc::now())
+}
+
+fn parse_date_at(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+  let value = value.trim();
+
+  if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+    return Ok(date.with_timezone(&Utc));
+  }
+
+  if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+    return Ok(date.and_time(NaiveTime::MIN).and_utc());
+  }
+
+  let unit_start = value
+    .find(|c: char| !c.is_ascii_digit())
+    .unwrap_or(value.len());
+  let (amount, unit) = value.split_at(unit_start);
+  let amount: i64 = amount.parse().map_err(|_| {
+    format!("expected a date like 2024-01-31 or a relative value like 30d, got '{value}'")
+  })?;
+  let duration = match unit {
+    "h" => Duration::try_hours(amount),
+    "d" => Duration::try_days(amount),
+    "w" => Duration::try_weeks(amount),
+    _ => return Err(format!("unknown unit '{unit}' in '{value}', use h, d or w")),
+  };
+
+  duration
+    .and_then(|d| now.checked_sub_signed(d))
+    .ok_or_else(|| format!("'{value}' is too far in the past"))
+}
+
 pub fn group_by_author(input: Vec<Entry>) -> BTreeMap<String, Vec<Entry>> {
   // Use a BTreeMap since its keys are sorted alphabetically
   let mut groups: BTreeMap<String, Vec<Entry>> = BTreeMap::new();
@@ -25,18 +63,76 @@ pub fn group_by_author(input: Vec<Entry>) -> BTreeMap<String, Vec<Entry>> {
   groups
 }
 
-pub fn get_pr_link(entry: &Entry, owner: &str, repo: &str) -> String {
+fn pr_number_key(entry: &Entry) -> u64 {
+  // Entries without a PR number go last
+  entry
+    .pr_number
+    .as_deref()
+    .and_then(|n| n.parse().ok())
+    .unwrap_or(u64::MAX)
+}
+
+pub fn group_and_sort(input: Vec<Entry>, sort: SortOrder) -> Vec<(String, Vec<Entry>)> {
+  let mut first_seen: HashMap<String, usize> = HashMap::new();
+  for (index, e) in input.iter().enumerate() {
+    first_seen.entry(e.author.clone()).or_insert(index);
+  }
+
+  let mut groups: Vec<(String, Vec<Entry>)> = group_by_author(input).into_iter().collect();
+
+  for (_, entries) in groups.iter_mut() {
+    match sort {
+      SortOrder::Alphabetical => entries.sort_by_cached_key(|e| {
+        e.message
+          .as_deref()
+          .map(str::to_lowercase)
+          .unwrap_or_default()
+      }),
+      SortOrder::Pr => entries.sort_by_key(pr_number_key),
+      // Entries are already in the order of the commits
+      SortOrder::Contributions | SortOrder::First => {}
+    }
+  }
+
+  match sort {
+    SortOrder::Alphabetical => groups.sort_by_cached_key(|(author, _)| author.to_lowercase()),
+    SortOrder::Contributions => groups.sort_by(|(a, a_entries), (b, b_entries)| {
+      b_entries
+        .len()
+        .cmp(&a_entries.len())
+        .then_with(|| a.to_lowercase().cmp(&b.to_lowercase()))
+    }),
+    SortOrder::First => groups.sort_by_key(|(author, _)| first_seen[author]),
+    SortOrder::Pr => {
+      groups.sort_by_key(|(_, entries)| entries.iter().map(pr_number_key).min().unwrap_or(u64::MAX))
+    }
+  }
+
+  groups
+}
+
+pub fn get_pr_link(entry: &Entry, urls: &RepoUrls) -> String {
   let Some(number) = &entry.pr_number else {
-    return String::from("");
+    // Commits pushed directly (without a PR) link to the commit itself
+    return match &entry.sha {
+      Some(sha) => urls.commit_link(sha),
+      None => String::from(""),
+    };
   };
 
-  format!("[PR #{number}](https://github.com/{owner}/{repo}/pull/{number})")
+  let link = urls.pr_link(number);
+
+  match &entry.backport_of {
+    Some(original) => format!("{link} (backport of {})", urls.reference(original)),
+    None => link,
+  }
 }
 
 pub fn parse_msg_and_pr(input: &str) -> MsgAndPr {
   lazy_static! {
     // Regex is not dynamic so .unwrap is fine
-    static ref RE: Regex = Regex::new(r"^(?P<msg>.*)\(#(?P<pr>[0-9]+)\)").unwrap();
+    // GitHub references PRs with "#123", GitLab references MRs with "!123"
+    static ref RE: Regex = Regex::new(r"^(?P<msg>.*)\([#!](?P<pr>[0-9]+)\)").unwrap();
   }
 
   match RE.captures(input) {
@@ -53,17 +149,89 @@ pub fn parse_msg_and_pr(input: &str) -> MsgAndPr {
   }
 }
 
-pub fn create_entries(
-  commits: Vec<Commit>,
-  should_include_org_members: bool,
-  parsed_excludes: Vec<String>,
-  org_members: Vec<String>,
-) -> Vec<Entry> {
+/// Logins listed one per line, ignoring empty lines and `#` comments
+pub fn parse_logins(contents: &str) -> Vec<String> {
+  contents
+    .lines()
+    .map(|line| line.split('#').next().unwrap_or_default().trim())
+    .filter(|line| !line.is_empty())
+    .map(str::to_owned)
+    .collect()
+}
+
+pub fn parse_backport_ref(message: &str) -> Option<MsgAndPr> {
+  lazy_static! {
+    // Only a reference at the very end of the message counts as a backport
+    static ref RE: Regex = Regex::new(r"^(?P<msg>.*?)\s*\([#!](?P<pr>[0-9]+)\)$").unwrap();
+  }
+
+  RE.captures(message).map(|caps| MsgAndPr {
+    message: caps.name("msg").map(|m| m.as_str().to_string()),
+    pr_number: caps.name("pr").map(|m| m.as_str().to_string()),
+  })
+}
+
+pub fn dedupe_entries(entries: Vec<Entry>, strip_backport_refs: bool) -> Vec<Entry> {
+  // Backports are dropped in favour of their original PR, no matter which comes first
+  let originals: HashSet<String> = entries
+    .iter()
+    .filter(|e| e.message.as_deref().and_then(parse_backport_ref).is_none())
+    .filter_map(|e| e.pr_number.clone())
+    .collect();
+  let mut seen: HashSet<String> = HashSet::new();
+  let mut deduped = Vec::with_capacity(entries.len());
+
+  for mut entry in entries {
+    let Some(pr_number) = entry.pr_number.clone() else {
+      deduped.push(entry);
+      continue;
+    };
+
+    let backport_ref = entry.message.as_deref().and_then(parse_backport_ref);
+    let original = backport_ref
+      .as_ref()
+      .and_then(|b| b.pr_number.clone())
+      .unwrap_or_else(|| pr_number.clone());
+
+    if backport_ref.is_some() && originals.contains(&original) {
+      continue;
+    }
+
+    // A backport is a duplicate if either the original PR or the backport PR itself was already seen
+    let is_duplicate = seen.contains(&original) || seen.contains(&pr_number);
+    seen.insert(original);
+    seen.insert(pr_number);
+
+    if is_duplicate {
+      continue;
+    }
+
+    if strip_backport_refs {
+      if let Some(b) = backport_ref {
+        entry.message = b.message;
+        entry.backport_of = b.pr_number;
+      }
+    }
+
+    deduped.push(entry);
+  }
+
+  deduped
+}
+
+/// One entry per commit, use `is_excluded` to drop the ones of excluded authors
+pub fn create_entries(commits: Vec<Commit>) -> Vec<Entry> {
   commits
     .into_iter()
     .map(|c| {
       let first_line = c.commit.message.lines().next().map_or("", |f| f);
       let msg_and_pr = parse_msg_and_pr(first_line);
+      // Without a PR number fall back to the raw subject line
+      let message = match msg_and_pr.message {
+        Some(m) => Some(m),
+        None if !first_line.trim().is_empty() => Some(first_line.trim().to_string()),
+        None => None,
+      };
 
       let mut author = c.commit.author.name;
       let mut author_url: Option<String> = None;
@@ -73,28 +241,157 @@ pub fn create_entries(
         author_url = Some(a.html_url);
       };
 
+      let (resolved_pr, labels) = match c.pull_request {
+        Some(pr) => (Some(pr.number.to_string()), pr.labels),
+        None => (None, None),
+      };
+      // Prefer the number from the message, but fall back to the PR the backend resolved
+      let pr_number = msg_and_pr.pr_number.or(resolved_pr.clone());
+      // The labels belong to the resolved PR, which might not be the one in the message
+      let labels = labels.filter(|_| pr_number == resolved_pr);
+
       Entry {
         author,
         author_url,
-        message: msg_and_pr.message,
-        pr_number: msg_and_pr.pr_number,
+        message,
+        pr_number,
+        sha: Some(c.sha),
+        labels,
+        ..Default::default()
       }
     })
-    .filter(|i| {
-      if should_include_org_members {
-        true
-      } else {
-        // Exclude members from the final list of entries
-        !parsed_excludes
-          .iter()
-          .chain(&org_members)
-          .any(|x| x == &i.author)
+    .collect()
+}
+
+/// Whether the author of the entry is excluded or (unless they're included) an organization member
+pub fn is_excluded(
+  entry: &Entry,
+  should_include_org_members: bool,
+  parsed_excludes: &[String],
+  org_members: &[String],
+) -> bool {
+  if should_include_org_members {
+    return false;
+  }
+
+  parsed_excludes
+    .iter()
+    .chain(org_members)
+    .any(|x| x == &entry.author)
+}
+
+/// One entry per reviewer of each PR, except for the PR's author
+pub fn create_review_entries(
+  entries: &[Entry],
+  reviewers: &HashMap<String, Vec<CommitAuthor>>,
+) -> Vec<Entry> {
+  let mut review_entries = Vec::new();
+
+  for entry in entries {
+    let Some(pr_number) = &entry.pr_number else {
+      continue;
+    };
+    let mut seen = HashSet::new();
+
+    for reviewer in reviewers.get(pr_number).into_iter().flatten() {
+      if reviewer.login == entry.author || !seen.insert(&reviewer.login) {
+        continue;
       }
-    })
+
+      review_entries.push(Entry {
+        author: reviewer.login.clone(),
+        author_url: Some(reviewer.html_url.clone()),
+        message: entry.message.clone(),
+        pr_number: Some(pr_number.clone()),
+        ..Default::default()
+      });
+    }
+  }
+
+  review_entries
+}
+
+/// One entry per issue closed by the PRs, credited to the issue's author and linking the PR that fixed it
+pub fn create_report_entries(
+  entries: &[Entry],
+  issues: &HashMap<String, Vec<Issue>>,
+) -> Vec<Entry> {
+  let mut seen = HashSet::new();
+  let mut report_entries = Vec::new();
+
+  for entry in entries {
+    let Some(pr_number) = &entry.pr_number else {
+      continue;
+    };
+
+    for issue in issues.get(pr_number).into_iter().flatten() {
+      // Several PRs can close the same issue
+      if !seen.insert(issue.number) {
+        continue;
+      }
+      let Some(author) = &issue.author else {
+        continue;
+      };
+
+      report_entries.push(Entry {
+        author: author.login.clone(),
+        author_url: Some(author.html_url.clone()),
+        message: Some(issue.title.clone()),
+        pr_number: Some(pr_number.clone()),
+        ..Default::default()
+      });
+    }
+  }
+
+  report_entries
+}
+
+/// Section with its own heading, empty if there's nothing to list
+pub fn create_section_output(heading: &str, list: &str) -> String {
+  if list.is_empty() {
+    return String::new();
+  }
+
+  format!("\n### {heading}\n\n{list}")
+}
+
+/// Keeps the entries of the listed people (matching logins case-insensitively).
+/// Also returns the listed people without any entry.
+pub fn filter_only(entries: Vec<Entry>, only: &[String]) -> (Vec<Entry>, Vec<String>) {
+  let is_listed = |author: &str| only.iter().any(|o| o.eq_ignore_ascii_case(author));
+  let entries: Vec<Entry> = entries
+    .into_iter()
+    .filter(|e| is_listed(&e.author))
+    .collect();
+  let missing = only
+    .iter()
+    .filter(|o| !entries.iter().any(|e| o.eq_ignore_ascii_case(&e.author)))
+    .cloned()
+    .collect();
+
+  (entries, missing)
+}
+
+/// Drops the entries of authors with fewer than `min` contributions
+pub fn filter_min_contributions(entries: Vec<Entry>, min: usize) -> Vec<Entry> {
+  let mut counts: HashMap<String, usize> = HashMap::new();
+  for entry in &entries {
+    *counts.entry(entry.author.clone()).or_default() += 1;
+  }
+
+  entries
+    .into_iter()
+    .filter(|e| counts[&e.author] >= min)
     .collect()
 }
 
-pub fn create_output(groups: BTreeMap<String, Vec<Entry>>, owner: &str, repo: &str) -> String {
+/// Markdown list of the entries grouped by author.
+/// With `max_entries_per_author`, the remaining entries of an author are collapsed into a single line.
+pub fn create_output(
+  groups: impl IntoIterator<Item = (String, Vec<Entry>)>,
+  urls: &RepoUrls,
+  max_entries_per_author: Option<usize>,
+) -> String {
   let mut output = String::new();
 
   for (author_name, author_entries) in groups {
@@ -104,19 +401,38 @@ pub fn create_output(groups: BTreeMap<String, Vec<Entry>>, owner: &str, repo: &s
     };
 
     if author_entries.len() > 1 {
+      let max = max_entries_per_author.unwrap_or(usize::MAX);
+      let remaining = author_entries.iter().skip(max);
+      let remaining_prs = remaining.clone().filter(|e| e.pr_number.is_some()).count();
+      let remaining_commits = remaining.count() - remaining_prs;
+      // Only accounts (with a URL) can be searched for
+      let search_url = author_entries[0]
+        .author_url
+        .as_ref()
+        .and_then(|_| urls.pr_search_url(&author_entries[0].author));
+
       let mut md_author_list = String::new();
-      for entry in author_entries {
+      for entry in author_entries.into_iter().take(max) {
         if let Some(msg) = &entry.message {
-          let line = format!("  - {} {}\n", msg, get_pr_link(&entry, owner, repo));
+          let line = format!("  - {} {}\n", msg, get_pr_link(&entry, urls));
           md_author_list.push_str(&line)
         };
       }
 
+      if remaining_prs + remaining_commits > 0 {
+        let more = get_more_text(remaining_prs, remaining_commits);
+        let line = match search_url {
+          Some(url) => format!("  - [{more}]({url})\n"),
+          None => format!("  - {more}\n"),
+        };
+        md_author_list.push_str(&line);
+      }
+
       let text = format!("- {md_author}\n{md_author_list}");
 
       output.push_str(&text);
     } else {
-      let pr_link = get_pr_link(&author_entries[0], owner, repo);
+      let pr_link = get_pr_link(&author_entries[0], urls);
 
       if let Some(msg) = &author_entries[0].message {
         let text = format!("- {md_author}: {msg} {pr_link}\n");
@@ -128,23 +444,258 @@ pub fn create_output(groups: BTreeMap<String, Vec<Entry>>, owner: &str, repo: &s
   output
 }
 
+/// e.g. "…and 2 more PRs and 1 more commit", for the collapsed entries of an author
+fn get_more_text(prs: usize, commits: usize) -> String {
+  let parts: Vec<String> = [(prs, "PR"), (commits, "commit")]
+    .into_iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, noun)| match count {
+      1 => format!("1 more {noun}"),
+      _ => format!("{count} more {noun}s"),
+    })
+    .collect();
+
+  format!("…and {}", parts.join(" and "))
+}
+
+pub fn create_first_time_output(
+  groups: &[(String, Vec<Entry>)],
+  first_time_contributors: &HashSet<String>,
+) -> String {
+  let mut output = String::new();
+
+  for (author_name, author_entries) in groups {
+    // The group name might be a display name, so check against the login
+    if !first_time_contributors.contains(&author_entries[0].author) {
+      continue;
+    }
+
+    let line = match &author_entries[0].author_url {
+      Some(url) => format!("- [{author_name}]({url})\n"),
+      None => format!("- {author_name}\n"),
+    };
+    output.push_str(&line);
+  }
+
+  if output.is_empty() {
+    return output;
+  }
+
+  format!("\n### 🎉 First-time contributors\n\n{output}")
+}
+
+pub fn get_display_name(login: &str, user: Option<&User>) -> String {
+  match user.and_then(|u| u.name.as_deref()) {
+    Some(name) if !name.trim().is_empty() => format!("{} (@{login})", name.trim()),
+    _ => login.to_string(),
+  }
+}
+
+/// "Name (@login) · Company · blog", leaving out what isn't set, used as the tooltip of the avatars
+pub fn get_profile_summary(login: &str, user: &User) -> String {
+  let non_empty = |value: &Option<String>| {
+    value
+      .as_deref()
+      .map(str::trim)
+      .filter(|v| !v.is_empty())
+      .map(str::to_string)
+  };
+
+  std::iter::once(get_display_name(login, Some(user)))
+    .chain(non_empty(&user.company))
+    .chain(non_empty(&user.blog))
+    .collect::<Vec<_>>()
+    .join(" · ")
+}
+
+fn escape_attribute(value: &str) -> String {
+  value
+    .replace('&', "&amp;")
+    .replace('"', "&quot;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+}
+
+/// Replaces the login of each group with "Name (@login)" if the user has a name set
+pub fn with_display_names(
+  groups: Vec<(String, Vec<Entry>)>,
+  users: &HashMap<String, User>,
+) -> Vec<(String, Vec<Entry>)> {
+  groups
+    .into_iter()
+    .map(|(author, entries)| (get_display_name(&author, users.get(&author)), entries))
+    .collect()
+}
+
+/// Drops entries with one of the `exclude` labels and, if `include` isn't empty, the ones without any of them
+pub fn filter_labels(entries: Vec<Entry>, include: &[String], exclude: &[String]) -> Vec<Entry> {
+  let has_any = |entry: &Entry, labels: &[String]| {
+    entry
+      .labels
+      .iter()
+      .flatten()
+      .any(|l| labels.iter().any(|x| x.eq_ignore_ascii_case(l)))
+  };
+
+  entries
+    .into_iter()
+    .filter(|e| !has_any(e, exclude))
+    .filter(|e| include.is_empty() || has_any(e, include))
+    .collect()
+}
+
+/// Appends the tag of each label, e.g. "📝 docs" for "documentation", to the message of the entries
+pub fn with_label_tags(entries: Vec<Entry>, tags: &[(String, String)]) -> Vec<Entry> {
+  entries
+    .into_iter()
+    .map(|mut entry| {
+      let entry_tags: Vec<&str> = tags
+        .iter()
+        .filter(|(label, _)| {
+          entry
+            .labels
+            .iter()
+            .flatten()
+            .any(|l| l.eq_ignore_ascii_case(label))
+        })
+        .map(|(_, tag)| tag.as_str())
+        .collect();
+
+      if let (Some(message), false) = (&mut entry.message, entry_tags.is_empty()) {
+        message.push(' ');
+        message.push_str(&entry_tags.join(" "));
+      }
+
+      entry
+    })
+    .collect()
+}
+
+pub fn create_avatar_output(
+  groups: &[(String, Vec<Entry>)],
+  users: &HashMap<String, User>,
+  size: u32,
+) -> String {
+  let avatars = groups
+    .iter()
+    .filter_map(|(_, entries)| {
+      let login = &entries[0].author;
+      let user = users.get(login)?;
+      let url = entries[0].author_url.as_deref().unwrap_or_default();
+
+      // GitHub's avatar URLs usually have a query string already, but not all of them do
+      let separator = if user.avatar_url.contains('?') { '&' } else { '?' };
+
+      Some(format!(
+        "<a href=\"{url}\"><img src=\"{avatar}{separator}s={size}\" width=\"{size}\" height=\"{size}\" alt=\"{login}\" title=\"{title}\" /></a>",
+        avatar = user.avatar_url,
+        title = escape_attribute(&get_profile_summary(login, user))
+      ))
+    })
+    .collect::<Vec<_>>();
+
+  if avatars.is_empty() {
+    return String::new();
+  }
+
+  format!("\n{}\n", avatars.join("\n"))
+}
+
+/// Summary of the range, `entries` being one entry per commit of an external contributor
+pub fn create_stats(total_commits: usize, entries: &[Entry], top_count: usize) -> Stats {
+  let mut counts: HashMap<&str, usize> = HashMap::new();
+  for e in entries {
+    *counts.entry(&e.author).or_default() += 1;
+  }
+
+  let pull_requests = entries
+    .iter()
+    .filter_map(|e| e.pr_number.as_deref())
+    .collect::<HashSet<_>>()
+    .len();
+
+  let mut top_contributors: Vec<(String, usize)> = counts
+    .iter()
+    .map(|(author, count)| (author.to_string(), *count))
+    .collect();
+  // Highest count first, ties are sorted by name so that the output is stable
+  top_contributors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+  top_contributors.truncate(top_count);
+
+  Stats {
+    total_commits,
+    external_commits: entries.len(),
+    unique_contributors: counts.len(),
+    pull_requests,
+    top_contributors,
+  }
+}
+
+pub fn create_stats_output(stats: &Stats) -> String {
+  let top = stats
+    .top_contributors
+    .iter()
+    .map(|(author, count)| format!("{author} ({count})"))
+    .collect::<Vec<_>>()
+    .join(", ");
+
+  let mut output = format!(
+    "- Commits: {}\n- Commits by external contributors: {}\n- Contributors: {}\n- Pull requests: {}\n",
+    stats.total_commits, stats.external_commits, stats.unique_contributors, stats.pull_requests
+  );
+
+  if !top.is_empty() {
+    output.push_str(&format!("- Top contributors: {top}\n"));
+  }
+
+  format!("### Summary\n\n{output}\n")
+}
+
+#[derive(Eq, PartialEq, Debug)]
+pub struct Stats {
+  /// All commits in the range, including the ones by org members and excludes
+  pub total_commits: usize,
+  /// Commits left after excluding org members and excludes, before any deduplication or threshold
+  pub external_commits: usize,
+  pub unique_contributors: usize,
+  pub pull_requests: usize,
+  pub top_contributors: Vec<(String, usize)>,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, ValueEnum)]
+pub enum SortOrder {
+  /// Case-insensitive by login and message
+  Alphabetical,
+  /// Most contributions first
+  Contributions,
+  /// By first contribution in the range
+  First,
+  /// By PR number
+  Pr,
+}
+
 #[derive(Eq, PartialEq, Debug)]
 pub struct MsgAndPr {
   pub message: Option<String>,
   pub pr_number: Option<String>,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
 pub struct Entry {
   pub author: String,
   pub author_url: Option<String>,
   pub message: Option<String>,
   pub pr_number: Option<String>,
+  /// PR number this entry was cherry-picked from, if the reference was stripped from the message
+  pub backport_of: Option<String>,
+  pub sha: Option<String>,
+  /// Labels of the PR, `None` if they weren't resolved (yet)
+  pub labels: Option<Vec<String>>,
 }
 
 #[cfg(test)]
 mod tests {
-  use crate::api::{CommitAuthor, CommitMeta, CommitMetaAuthor};
+  use crate::api::{CommitAuthor, CommitMeta, CommitMetaAuthor, PullRequestRef};
 
   use super::*;
 
@@ -154,6 +705,7 @@ mod tests {
       author_url: Some("author-a-url".to_string()),
       message: Some("fix(scope): Message".to_string()),
       pr_number: Some("1".to_string()),
+      ..Default::default()
     }
   }
 
@@ -163,6 +715,7 @@ mod tests {
       author_url: Some("author-a-url".to_string()),
       message: Some("fix(scope): Message".to_string()),
       pr_number: Some("2".to_string()),
+      ..Default::default()
     }
   }
 
@@ -172,6 +725,7 @@ mod tests {
       author_url: Some("author-b-url".to_string()),
       message: Some("fix(scope): Message".to_string()),
       pr_number: Some("3".to_string()),
+      ..Default::default()
     }
   }
 
@@ -181,9 +735,177 @@ mod tests {
       author_url: None,
       message: Some("fix(scope): Message".to_string()),
       pr_number: Some("4".to_string()),
+      ..Default::default()
     }
   }
 
+  #[test]
+  fn filter_only_correct() {
+    let entries = vec![
+      Entry {
+        author: "harry".to_string(),
+        ..Default::default()
+      },
+      Entry {
+        author: "Hermione".to_string(),
+        ..Default::default()
+      },
+    ];
+    let only = vec!["hermione".to_string(), "ron".to_string()];
+
+    let (entries, missing) = filter_only(entries, &only);
+
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].author, "Hermione");
+    assert_eq!(missing, vec!["ron"]);
+  }
+
+  #[test]
+  fn filter_labels_correct() {
+    let entry = |pr: &str, labels: &[&str]| Entry {
+      pr_number: Some(pr.to_string()),
+      labels: Some(labels.iter().map(|l| l.to_string()).collect()),
+      ..Default::default()
+    };
+    let entries = vec![
+      entry("1", &["documentation"]),
+      entry("2", &["skip-changelog", "documentation"]),
+      entry("3", &[]),
+    ];
+
+    let excluded = filter_labels(entries.clone(), &[], &["Skip-Changelog".to_string()]);
+    assert_eq!(excluded, vec![entries[0].clone(), entries[2].clone()]);
+
+    let included = filter_labels(
+      entries.clone(),
+      &["documentation".to_string()],
+      &["skip-changelog".to_string()],
+    );
+    assert_eq!(included, vec![entries[0].clone()]);
+  }
+
+  #[test]
+  fn with_label_tags_correct() {
+    let entries = vec![
+      Entry {
+        message: Some("docs: Explain gears".to_string()),
+        labels: Some(vec![
+          "documentation".to_string(),
+          "good first issue".to_string(),
+        ]),
+        ..Default::default()
+      },
+      Entry {
+        message: Some("fix: Oil the gears".to_string()),
+        ..Default::default()
+      },
+    ];
+    let tags = vec![
+      ("good first issue".to_string(), "🌱".to_string()),
+      ("documentation".to_string(), "📝 docs".to_string()),
+    ];
+
+    let entries = with_label_tags(entries, &tags);
+
+    assert_eq!(
+      entries[0].message.as_deref(),
+      Some("docs: Explain gears 🌱 📝 docs")
+    );
+    assert_eq!(entries[1].message.as_deref(), Some("fix: Oil the gears"));
+  }
+
+  #[test]
+  fn create_review_entries_correct() {
+    let reviewer = |login: &str| CommitAuthor {
+      login: login.to_string(),
+      html_url: format!("{login}-url"),
+    };
+    let mut reviewers = HashMap::new();
+    reviewers.insert(
+      "1".to_string(),
+      vec![
+        reviewer("author-b"),
+        reviewer("author-a"),
+        reviewer("author-b"),
+      ],
+    );
+
+    let entries = create_review_entries(&[entry_a_one(), entry_c()], &reviewers);
+
+    assert_eq!(
+      entries,
+      vec![Entry {
+        author: "author-b".to_string(),
+        author_url: Some("author-b-url".to_string()),
+        message: entry_a_one().message,
+        pr_number: Some("1".to_string()),
+        ..Default::default()
+      }]
+    );
+  }
+
+  #[test]
+  fn create_report_entries_correct() {
+    let issue = Issue {
+      number: 7,
+      title: "Gears are squeaking".to_string(),
+      author: Some(CommitAuthor {
+        login: "author-c".to_string(),
+        html_url: "author-c-url".to_string(),
+      }),
+    };
+    let mut issues = HashMap::new();
+    issues.insert("1".to_string(), vec![issue.clone()]);
+    issues.insert("2".to_string(), vec![issue]);
+
+    let entries = create_report_entries(&[entry_a_one(), entry_a_two()], &issues);
+
+    assert_eq!(
+      entries,
+      vec![Entry {
+        author: "author-c".to_string(),
+        author_url: Some("author-c-url".to_string()),
+        message: Some("Gears are squeaking".to_string()),
+        pr_number: Some("1".to_string()),
+        ..Default::default()
+      }]
+    );
+  }
+
+  #[test]
+  fn parse_date_correct() {
+    let now = DateTime::parse_from_rfc3339("2024-02-01T12:00:00Z")
+      .unwrap()
+      .with_timezone(&Utc);
+    let date = |value: &str| parse_date_at(value, now).map(|d| d.to_rfc3339());
+
+    assert_eq!(
+      date("2024-01-01"),
+      Ok("2024-01-01T00:00:00+00:00".to_string())
+    );
+    assert_eq!(
+      date("2024-01-15T08:30:00+02:00"),
+      Ok("2024-01-15T06:30:00+00:00".to_string())
+    );
+    assert_eq!(date("30d"), Ok("2024-01-02T12:00:00+00:00".to_string()));
+    assert_eq!(date("1w"), Ok("2024-01-25T12:00:00+00:00".to_string()));
+    assert_eq!(date("12h"), Ok("2024-02-01T00:00:00+00:00".to_string()));
+    assert!(date("30y").is_err());
+    assert!(date("999999999999d").is_err());
+    assert!(date("99999999999999999999h").is_err());
+    assert!(date("last month").is_err());
+  }
+
+  #[test]
+  fn parse_logins_correct() {
+    let contents = "# Core team\nLekoArts\n\n  pieh  # on leave\nrenovate[bot]\n";
+
+    assert_eq!(
+      parse_logins(contents),
+      vec!["LekoArts", "pieh", "renovate[bot]"]
+    );
+  }
+
   #[test]
   fn group_by_author_correct() {
     let input: Vec<Entry> = vec![entry_a_one(), entry_a_two(), entry_b()];
@@ -200,6 +922,96 @@ mod tests {
     assert_eq!(group_by_author(vec![]), BTreeMap::new())
   }
 
+  fn entry_with(author: &str, message: &str, pr_number: &str) -> Entry {
+    Entry {
+      author: author.to_string(),
+      message: Some(message.to_string()),
+      pr_number: Some(pr_number.to_string()),
+      ..Default::default()
+    }
+  }
+
+  fn sort_input() -> Vec<Entry> {
+    vec![
+      entry_with("Zed", "b", "5"),
+      entry_with("alice", "B", "3"),
+      entry_with("bob", "c", "1"),
+      entry_with("alice", "a", "4"),
+      entry_with("bob", "d", "2"),
+      entry_with("bob", "e", "6"),
+    ]
+  }
+
+  fn sorted_authors_and_prs(groups: Vec<(String, Vec<Entry>)>) -> Vec<(String, Vec<String>)> {
+    groups
+      .into_iter()
+      .map(|(author, entries)| {
+        let prs = entries.into_iter().filter_map(|e| e.pr_number).collect();
+        (author, prs)
+      })
+      .collect()
+  }
+
+  fn expected(input: &[(&str, &[&str])]) -> Vec<(String, Vec<String>)> {
+    input
+      .iter()
+      .map(|(author, prs)| {
+        (
+          author.to_string(),
+          prs.iter().map(|p| p.to_string()).collect(),
+        )
+      })
+      .collect()
+  }
+
+  #[test]
+  fn group_and_sort_alphabetical() {
+    assert_eq!(
+      sorted_authors_and_prs(group_and_sort(sort_input(), SortOrder::Alphabetical)),
+      expected(&[
+        ("alice", &["4", "3"]),
+        ("bob", &["1", "2", "6"]),
+        ("Zed", &["5"])
+      ])
+    )
+  }
+
+  #[test]
+  fn group_and_sort_contributions() {
+    assert_eq!(
+      sorted_authors_and_prs(group_and_sort(sort_input(), SortOrder::Contributions)),
+      expected(&[
+        ("bob", &["1", "2", "6"]),
+        ("alice", &["3", "4"]),
+        ("Zed", &["5"])
+      ])
+    )
+  }
+
+  #[test]
+  fn group_and_sort_first() {
+    assert_eq!(
+      sorted_authors_and_prs(group_and_sort(sort_input(), SortOrder::First)),
+      expected(&[
+        ("Zed", &["5"]),
+        ("alice", &["3", "4"]),
+        ("bob", &["1", "2", "6"])
+      ])
+    )
+  }
+
+  #[test]
+  fn group_and_sort_pr() {
+    assert_eq!(
+      sorted_authors_and_prs(group_and_sort(sort_input(), SortOrder::Pr)),
+      expected(&[
+        ("bob", &["1", "2", "6"]),
+        ("alice", &["3", "4"]),
+        ("Zed", &["5"])
+      ])
+    )
+  }
+
   #[test]
   fn pr_link_no_number() {
     assert_eq!(
@@ -209,9 +1021,9 @@ mod tests {
           author_url: None,
           message: None,
           pr_number: None,
+          ..Default::default()
         },
-        "owner",
-        "repo"
+        &RepoUrls::github("owner", "repo")
       ),
       "".to_string()
     )
@@ -226,14 +1038,32 @@ mod tests {
           author_url: None,
           message: None,
           pr_number: Some("123".to_string()),
+          ..Default::default()
         },
-        "owner",
-        "repo"
+        &RepoUrls::github("owner", "repo")
       ),
       "[PR #123](https://github.com/owner/repo/pull/123)".to_string()
     )
   }
 
+  #[test]
+  fn pr_link_falls_back_to_commit() {
+    assert_eq!(
+      get_pr_link(
+        &Entry {
+          author: "foo".to_string(),
+          author_url: None,
+          message: None,
+          pr_number: None,
+          sha: Some("0123456789abcdef".to_string()),
+          ..Default::default()
+        },
+        &RepoUrls::github("owner", "repo")
+      ),
+      "[Commit 0123456](https://github.com/owner/repo/commit/0123456789abcdef)".to_string()
+    )
+  }
+
   #[test]
   fn parse_msg_and_pr_no_result() {
     assert_eq!(
@@ -267,6 +1097,17 @@ mod tests {
     )
   }
 
+  #[test]
+  fn parse_msg_and_pr_merge_request() {
+    assert_eq!(
+      parse_msg_and_pr("fix(scope): Message (!123)"),
+      MsgAndPr {
+        message: Some("fix(scope): Message".to_string()),
+        pr_number: Some("123".to_string())
+      }
+    )
+  }
+
   #[test]
   fn parse_msg_and_pr_only_msg() {
     assert_eq!(
@@ -278,6 +1119,100 @@ mod tests {
     )
   }
 
+  #[test]
+  fn parse_backport_ref_correct() {
+    assert_eq!(
+      parse_backport_ref("fix(scope): Message (#123)"),
+      Some(MsgAndPr {
+        message: Some("fix(scope): Message".to_string()),
+        pr_number: Some("123".to_string())
+      })
+    )
+  }
+
+  #[test]
+  fn parse_backport_ref_not_at_end() {
+    assert_eq!(parse_backport_ref("Revert (#123) partially"), None)
+  }
+
+  fn entry_backport() -> Entry {
+    Entry {
+      author: "author-a".to_string(),
+      author_url: Some("author-a-url".to_string()),
+      message: Some("fix(scope): Message (#1)".to_string()),
+      pr_number: Some("10".to_string()),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn dedupe_entries_same_pr_number() {
+    assert_eq!(
+      dedupe_entries(vec![entry_a_one(), entry_a_one(), entry_b()], false),
+      vec![entry_a_one(), entry_b()]
+    )
+  }
+
+  #[test]
+  fn dedupe_entries_backport_of_included_pr() {
+    assert_eq!(
+      dedupe_entries(vec![entry_a_one(), entry_backport(), entry_b()], false),
+      vec![entry_a_one(), entry_b()]
+    )
+  }
+
+  #[test]
+  fn dedupe_entries_backport_before_original() {
+    assert_eq!(
+      dedupe_entries(vec![entry_backport(), entry_b(), entry_a_one()], false),
+      vec![entry_b(), entry_a_one()]
+    )
+  }
+
+  #[test]
+  fn dedupe_entries_keeps_backport_ref() {
+    assert_eq!(
+      dedupe_entries(vec![entry_backport()], false),
+      vec![entry_backport()]
+    )
+  }
+
+  #[test]
+  fn dedupe_entries_strip_backport_ref() {
+    assert_eq!(
+      dedupe_entries(vec![entry_backport(), entry_b()], true),
+      vec![
+        Entry {
+          author: "author-a".to_string(),
+          author_url: Some("author-a-url".to_string()),
+          message: Some("fix(scope): Message".to_string()),
+          pr_number: Some("10".to_string()),
+          backport_of: Some("1".to_string()),
+          ..Default::default()
+        },
+        entry_b()
+      ]
+    )
+  }
+
+  #[test]
+  fn pr_link_backport() {
+    assert_eq!(
+      get_pr_link(
+        &Entry {
+          author: "foo".to_string(),
+          author_url: None,
+          message: None,
+          pr_number: Some("456".to_string()),
+          backport_of: Some("123".to_string()),
+          ..Default::default()
+        },
+        &RepoUrls::github("owner", "repo")
+      ),
+      "[PR #456](https://github.com/owner/repo/pull/456) (backport of #123)".to_string()
+    )
+  }
+
   fn get_org_members() -> Vec<String> {
     vec!["author-d".to_string()]
   }
@@ -289,117 +1224,157 @@ mod tests {
   fn commits_data() -> Vec<Commit> {
     vec![
       Commit {
+        sha: "sha-1".to_string(),
         url: "url-1".to_string(),
         commit: CommitMeta {
           url: "url-1".to_string(),
           message: "fix(scope): Message (#1)".to_string(),
           author: CommitMetaAuthor {
             name: "author-a".to_string(),
+            email: "author-a@example.com".to_string(),
           },
         },
         author: Some(CommitAuthor {
           login: "author-a".to_string(),
           html_url: "author-a-url".to_string(),
         }),
+        pull_request: None,
       },
       Commit {
+        sha: "sha-2".to_string(),
         url: "url-2".to_string(),
         commit: CommitMeta {
           url: "url-2".to_string(),
           message: "fix(scope): Message (#2)".to_string(),
           author: CommitMetaAuthor {
             name: "author-a".to_string(),
+            email: "author-a@example.com".to_string(),
           },
         },
         author: Some(CommitAuthor {
           login: "author-a".to_string(),
           html_url: "author-a-url".to_string(),
         }),
+        pull_request: None,
       },
       Commit {
+        sha: "sha-3".to_string(),
         url: "url-3".to_string(),
         commit: CommitMeta {
           url: "url-3".to_string(),
           message: "fix(scope): Message (#3)".to_string(),
           author: CommitMetaAuthor {
             name: "author-b".to_string(),
+            email: "author-b@example.com".to_string(),
           },
         },
         author: Some(CommitAuthor {
           login: "author-b".to_string(),
           html_url: "author-b-url".to_string(),
         }),
+        pull_request: None,
       },
       Commit {
+        sha: "sha-4".to_string(),
         url: "url-4".to_string(),
         commit: CommitMeta {
           url: "url-4".to_string(),
           message: "fix(scope): Message (#4)".to_string(),
           author: CommitMetaAuthor {
             name: "author-c".to_string(),
+            email: "author-c@example.com".to_string(),
           },
         },
         author: None,
+        pull_request: None,
       },
       Commit {
+        sha: "sha-5".to_string(),
         url: "url-5".to_string(),
         commit: CommitMeta {
           url: "url-5".to_string(),
           message: "fix(scope): Message (#5)".to_string(),
           author: CommitMetaAuthor {
             name: "author-d".to_string(),
+            email: "author-d@example.com".to_string(),
           },
         },
         author: None,
+        pull_request: None,
       },
       Commit {
+        sha: "sha-6".to_string(),
         url: "url-6".to_string(),
         commit: CommitMeta {
           url: "url-6".to_string(),
           message: "fix(scope): Message (#6)".to_string(),
           author: CommitMetaAuthor {
             name: "author-e".to_string(),
+            email: "author-e@example.com".to_string(),
           },
         },
         author: None,
+        pull_request: None,
       },
     ]
   }
 
+  /// Entries of `commits_data` without the ones of excluded authors, like the CLI does it
+  fn excluded_entries(
+    should_include_org_members: bool,
+    excludes: &[String],
+    org_members: &[String],
+  ) -> Vec<Entry> {
+    create_entries(commits_data())
+      .into_iter()
+      .filter(|e| !is_excluded(e, should_include_org_members, excludes, org_members))
+      .collect()
+  }
+
   #[test]
   fn create_entries_defaults() {
     assert_eq!(
-      create_entries(commits_data(), false, vec![], get_org_members()),
+      excluded_entries(false, &[], &get_org_members()),
       vec![
         Entry {
           author: "author-a".to_string(),
           author_url: Some("author-a-url".to_string()),
           message: Some("fix(scope): Message".to_string()),
-          pr_number: Some("1".to_string())
+          pr_number: Some("1".to_string()),
+          sha: Some("sha-1".to_string()),
+          ..Default::default()
         },
         Entry {
           author: "author-a".to_string(),
           author_url: Some("author-a-url".to_string()),
           message: Some("fix(scope): Message".to_string()),
-          pr_number: Some("2".to_string())
+          pr_number: Some("2".to_string()),
+          sha: Some("sha-2".to_string()),
+          ..Default::default()
         },
         Entry {
           author: "author-b".to_string(),
           author_url: Some("author-b-url".to_string()),
           message: Some("fix(scope): Message".to_string()),
-          pr_number: Some("3".to_string())
+          pr_number: Some("3".to_string()),
+          sha: Some("sha-3".to_string()),
+          ..Default::default()
         },
         Entry {
           author: "author-c".to_string(),
           author_url: None,
           message: Some("fix(scope): Message".to_string()),
-          pr_number: Some("4".to_string())
+          pr_number: Some("4".to_string()),
+          sha: Some("sha-4".to_string()),
+          ..Default::default()
         },
         Entry {
           author: "author-e".to_string(),
           author_url: None,
           message: Some("fix(scope): Message".to_string()),
-          pr_number: Some("6".to_string())
+          pr_number: Some("6".to_string()),
+          sha: Some("sha-6".to_string()),
+          ..Default::default()
         }
       ]
     )
@@ -408,43 +1383,55 @@ mod tests {
   #[test]
   fn create_entries_include_org_members() {
     assert_eq!(
-      create_entries(commits_data(), true, vec![], get_org_members()),
+      excluded_entries(true, &[], &get_org_members()),
       vec![
         Entry {
           author: "author-a".to_string(),
           author_url: Some("author-a-url".to_string()),
           message: Some("fix(scope): Message".to_string()),
-          pr_number: Some("1".to_string())
+          pr_number: Some("1".to_string()),
+          sha: Some("sha-1".to_string()),
+          ..Default::default()
         },
         Entry {
           author: "author-a".to_string(),
           author_url: Some("author-a-url".to_string()),
           message: Some("fix(scope): Message".to_string()),
-          pr_number: Some("2".to_string())
+          pr_number: Some("2".to_string()),
+          sha: Some("sha-2".to_string()),
+          ..Default::default()
         },
         Entry {
           author: "author-b".to_string(),
           author_url: Some("author-b-url".to_string()),
           message: Some("fix(scope): Message".to_string()),
-          pr_number: Some("3".to_string())
+          pr_number: Some("3".to_string()),
+          sha: Some("sha-3".to_string()),
+          ..Default::default()
         },
         Entry {
           author: "author-c".to_string(),
           author_url: None,
           message: Some("fix(scope): Message".to_string()),
-          pr_number: Some("4".to_string())
+          pr_number: Some("4".to_string()),
+          sha: Some("sha-4".to_string()),
+          ..Default::default()
         },
         Entry {
           author: "author-d".to_string(),
           author_url: None,
           message: Some("fix(scope): Message".to_string()),
-          pr_number: Some("5".to_string())
+          pr_number: Some("5".to_string()),
+          sha: Some("sha-5".to_string()),
+          ..Default::default()
         },
         Entry {
           author: "author-e".to_string(),
           author_url: None,
           message: Some("fix(scope): Message".to_string()),
-          pr_number: Some("6".to_string())
+          pr_number: Some("6".to_string()),
+          sha: Some("sha-6".to_string()),
+          ..Default::default()
         }
       ]
     )
@@ -453,42 +1440,110 @@ mod tests {
   #[test]
   fn create_entries_defaults_with_excludes() {
     assert_eq!(
-      create_entries(commits_data(), false, get_excludes(), get_org_members()),
+      excluded_entries(false, &get_excludes(), &get_org_members()),
       vec![
         Entry {
           author: "author-a".to_string(),
           author_url: Some("author-a-url".to_string()),
           message: Some("fix(scope): Message".to_string()),
-          pr_number: Some("1".to_string())
+          pr_number: Some("1".to_string()),
+          sha: Some("sha-1".to_string()),
+          ..Default::default()
         },
         Entry {
           author: "author-a".to_string(),
           author_url: Some("author-a-url".to_string()),
           message: Some("fix(scope): Message".to_string()),
-          pr_number: Some("2".to_string())
+          pr_number: Some("2".to_string()),
+          sha: Some("sha-2".to_string()),
+          ..Default::default()
         },
         Entry {
           author: "author-b".to_string(),
           author_url: Some("author-b-url".to_string()),
           message: Some("fix(scope): Message".to_string()),
-          pr_number: Some("3".to_string())
+          pr_number: Some("3".to_string()),
+          sha: Some("sha-3".to_string()),
+          ..Default::default()
         },
         Entry {
           author: "author-c".to_string(),
           author_url: None,
           message: Some("fix(scope): Message".to_string()),
-          pr_number: Some("4".to_string())
+          pr_number: Some("4".to_string()),
+          sha: Some("sha-4".to_string()),
+          ..Default::default()
         }
       ]
     )
   }
 
+  #[test]
+  fn create_entries_without_pr_number() {
+    let commits = vec![Commit {
+      sha: "sha-7".to_string(),
+      url: "url-7".to_string(),
+      commit: CommitMeta {
+        url: "url-7".to_string(),
+        message: "chore: Direct push\n\nSome body".to_string(),
+        author: CommitMetaAuthor {
+          name: "author-f".to_string(),
+          email: "author-f@example.com".to_string(),
+        },
+      },
+      author: None,
+      pull_request: None,
+    }];
+
+    assert_eq!(
+      create_entries(commits),
+      vec![Entry {
+        author: "author-f".to_string(),
+        author_url: None,
+        message: Some("chore: Direct push".to_string()),
+        pr_number: None,
+        sha: Some("sha-7".to_string()),
+        ..Default::default()
+      }]
+    )
+  }
+
+  #[test]
+  fn create_entries_drops_labels_of_other_pr() {
+    let commit = |message: &str| Commit {
+      sha: "sha-8".to_string(),
+      url: "url-8".to_string(),
+      commit: CommitMeta {
+        url: "url-8".to_string(),
+        message: message.to_string(),
+        author: CommitMetaAuthor {
+          name: "author-f".to_string(),
+          email: "author-f@example.com".to_string(),
+        },
+      },
+      author: None,
+      pull_request: Some(PullRequestRef {
+        number: 8,
+        labels: Some(vec!["feature".to_string()]),
+      }),
+    };
+
+    let entries = create_entries(vec![
+      commit("feat: Squashed (#8)"),
+      commit("feat: Backport (#5)"),
+    ]);
+
+    assert_eq!(entries[0].labels, Some(vec!["feature".to_string()]));
+    // #5 still has to be looked up
+    assert_eq!(entries[1].labels, None);
+  }
+
   #[test]
   fn create_output_single_entry() {
     let mut groups = BTreeMap::new();
     groups.insert("author-b".to_string(), vec![entry_b()]);
 
-    assert_eq!(create_output(groups, "owner", "repo"), "- [author-b](author-b-url): fix(scope): Message [PR #3](https://github.com/owner/repo/pull/3)\n".to_string())
+    assert_eq!(create_output(groups, &RepoUrls::github("owner", "repo"), None), "- [author-b](author-b-url): fix(scope): Message [PR #3](https://github.com/owner/repo/pull/3)\n".to_string())
   }
 
   #[test]
@@ -496,7 +1551,53 @@ mod tests {
     let mut groups = BTreeMap::new();
     groups.insert("author-a".to_string(), vec![entry_a_one(), entry_a_two()]);
 
-    assert_eq!(create_output(groups, "owner", "repo"), "- [author-a](author-a-url)\n  - fix(scope): Message [PR #1](https://github.com/owner/repo/pull/1)\n  - fix(scope): Message [PR #2](https://github.com/owner/repo/pull/2)\n".to_string())
+    assert_eq!(create_output(groups, &RepoUrls::github("owner", "repo"), None), "- [author-a](author-a-url)\n  - fix(scope): Message [PR #1](https://github.com/owner/repo/pull/1)\n  - fix(scope): Message [PR #2](https://github.com/owner/repo/pull/2)\n".to_string())
+  }
+
+  #[test]
+  fn create_output_max_entries_per_author() {
+    let mut groups = BTreeMap::new();
+    groups.insert(
+      "author-a".to_string(),
+      vec![entry_a_one(), entry_a_two(), entry_a_two()],
+    );
+    let urls = RepoUrls::github("owner", "repo").merged_between("2022-11-01", "2022-11-08");
+
+    assert_eq!(create_output(groups, &urls, Some(1)), "- [author-a](author-a-url)\n  - fix(scope): Message [PR #1](https://github.com/owner/repo/pull/1)\n  - […and 2 more PRs](https://github.com/owner/repo/pulls?q=is:pr+is:merged+author:author-a+merged:2022-11-01..2022-11-08)\n".to_string())
+  }
+
+  #[test]
+  fn create_output_max_entries_per_author_with_commits() {
+    let commit = Entry {
+      pr_number: None,
+      sha: Some("0123456789abcdef".to_string()),
+      ..entry_c()
+    };
+    let mut groups = BTreeMap::new();
+    groups.insert(
+      "author-c".to_string(),
+      vec![entry_c(), entry_c(), entry_c(), commit],
+    );
+
+    assert_eq!(create_output(groups, &RepoUrls::github("owner", "repo"), Some(1)), "- author-c\n  - fix(scope): Message [PR #4](https://github.com/owner/repo/pull/4)\n  - …and 2 more PRs and 1 more commit\n".to_string())
+  }
+
+  #[test]
+  fn create_output_max_entries_per_author_no_author_url() {
+    let mut groups = BTreeMap::new();
+    groups.insert("author-c".to_string(), vec![entry_c(), entry_c()]);
+
+    assert_eq!(create_output(groups, &RepoUrls::github("owner", "repo"), Some(1)), "- author-c\n  - fix(scope): Message [PR #4](https://github.com/owner/repo/pull/4)\n  - …and 1 more PR\n".to_string())
+  }
+
+  #[test]
+  fn filter_min_contributions_correct() {
+    let entries = vec![entry_a_one(), entry_a_two(), entry_c()];
+
+    let entries = filter_min_contributions(entries, 2);
+
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|e| e.author == "author-a"));
   }
 
   #[test]
@@ -505,7 +1606,7 @@ mod tests {
     groups.insert("author-c".to_string(), vec![entry_c()]);
 
     assert_eq!(
-      create_output(groups, "owner", "repo"),
+      create_output(groups, &RepoUrls::github("owner", "repo"), None),
       "- author-c: fix(scope): Message [PR #4](https://github.com/owner/repo/pull/4)\n".to_string()
     )
   }
@@ -515,6 +1616,177 @@ mod tests {
     let mut groups = BTreeMap::new();
     groups.insert("author-c".to_string(), vec![entry_c(), entry_c()]);
 
-    assert_eq!(create_output(groups, "owner", "repo"), "- author-c\n  - fix(scope): Message [PR #4](https://github.com/owner/repo/pull/4)\n  - fix(scope): Message [PR #4](https://github.com/owner/repo/pull/4)\n".to_string())
+    assert_eq!(create_output(groups, &RepoUrls::github("owner", "repo"), None), "- author-c\n  - fix(scope): Message [PR #4](https://github.com/owner/repo/pull/4)\n  - fix(scope): Message [PR #4](https://github.com/owner/repo/pull/4)\n".to_string())
+  }
+
+  #[test]
+  fn create_first_time_output_correct() {
+    let groups = vec![
+      ("author-a".to_string(), vec![entry_a_one(), entry_a_two()]),
+      ("author-b".to_string(), vec![entry_b()]),
+    ];
+
+    let first_time_contributors = HashSet::from(["author-b".to_string()]);
+
+    assert_eq!(
+      create_first_time_output(&groups, &first_time_contributors),
+      "\n### 🎉 First-time contributors\n\n- [author-b](author-b-url)\n".to_string()
+    )
+  }
+
+  #[test]
+  fn create_first_time_output_empty() {
+    let groups = vec![("author-b".to_string(), vec![entry_b()])];
+
+    assert_eq!(
+      create_first_time_output(&groups, &HashSet::new()),
+      "".to_string()
+    )
+  }
+
+  #[test]
+  fn create_stats_correct() {
+    let entries = vec![
+      entry_a_one(),
+      entry_a_two(),
+      entry_b(),
+      entry_c(),
+      entry_c(),
+    ];
+
+    assert_eq!(
+      create_stats(10, &entries, 2),
+      Stats {
+        total_commits: 10,
+        external_commits: 5,
+        unique_contributors: 3,
+        pull_requests: 4,
+        top_contributors: vec![("author-a".to_string(), 2), ("author-c".to_string(), 2)],
+      }
+    )
+  }
+
+  #[test]
+  fn create_stats_output_correct() {
+    let stats = Stats {
+      total_commits: 10,
+      external_commits: 3,
+      unique_contributors: 2,
+      pull_requests: 3,
+      top_contributors: vec![("author-a".to_string(), 2), ("author-b".to_string(), 1)],
+    };
+
+    assert_eq!(create_stats_output(&stats), "### Summary\n\n- Commits: 10\n- Commits by external contributors: 3\n- Contributors: 2\n- Pull requests: 3\n- Top contributors: author-a (2), author-b (1)\n\n".to_string())
+  }
+
+  fn user_a(name: Option<&str>) -> User {
+    User {
+      login: "author-a".to_string(),
+      name: name.map(|n| n.to_string()),
+      avatar_url: "https://avatars.githubusercontent.com/u/1?v=4".to_string(),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn display_name_with_name() {
+    assert_eq!(
+      get_display_name("author-a", Some(&user_a(Some("Author A")))),
+      "Author A (@author-a)".to_string()
+    )
+  }
+
+  #[test]
+  fn display_name_without_name() {
+    assert_eq!(
+      get_display_name("author-a", Some(&user_a(None))),
+      "author-a".to_string()
+    );
+    assert_eq!(get_display_name("author-a", None), "author-a".to_string())
+  }
+
+  #[test]
+  fn create_output_with_display_names() {
+    let users = HashMap::from([(
+      "author-b".to_string(),
+      User {
+        login: "author-b".to_string(),
+        name: Some("Author B".to_string()),
+        ..Default::default()
+      },
+    )]);
+    let groups = with_display_names(vec![("author-b".to_string(), vec![entry_b()])], &users);
+
+    assert_eq!(create_output(groups, &RepoUrls::github("owner", "repo"), None), "- [Author B (@author-b)](author-b-url): fix(scope): Message [PR #3](https://github.com/owner/repo/pull/3)\n".to_string())
+  }
+
+  #[test]
+  fn create_avatar_output_without_query_string() {
+    let user = User {
+      avatar_url: "https://github.example.com/avatars/u/1".to_string(),
+      ..user_a(None)
+    };
+    let users = HashMap::from([("author-a".to_string(), user)]);
+    let groups = vec![("author-a".to_string(), vec![entry_a_one()])];
+
+    assert_eq!(
+      create_avatar_output(&groups, &users, 64),
+      "\n<a href=\"author-a-url\"><img src=\"https://github.example.com/avatars/u/1?s=64\" width=\"64\" height=\"64\" alt=\"author-a\" title=\"author-a\" /></a>\n".to_string()
+    )
+  }
+
+  #[test]
+  fn profile_summary_correct() {
+    let user = User {
+      company: Some("Acme".to_string()),
+      blog: Some("https://author-a.example.com".to_string()),
+      ..user_a(Some("Author A"))
+    };
+
+    assert_eq!(
+      get_profile_summary("author-a", &user),
+      "Author A (@author-a) · Acme · https://author-a.example.com".to_string()
+    )
+  }
+
+  #[test]
+  fn profile_summary_skips_empty_fields() {
+    let user = User {
+      company: Some(" ".to_string()),
+      blog: Some(String::new()),
+      ..user_a(None)
+    };
+
+    assert_eq!(
+      get_profile_summary("author-a", &user),
+      "author-a".to_string()
+    )
+  }
+
+  #[test]
+  fn create_avatar_output_escapes_title() {
+    let user = User {
+      company: Some("\"Acme\" & Co".to_string()),
+      ..user_a(Some("Author A"))
+    };
+    let users = HashMap::from([("author-a".to_string(), user)]);
+    let groups = vec![("author-a".to_string(), vec![entry_a_one()])];
+
+    assert!(create_avatar_output(&groups, &users, 64)
+      .contains("title=\"Author A (@author-a) · &quot;Acme&quot; &amp; Co\""))
+  }
+
+  #[test]
+  fn create_avatar_output_correct() {
+    let users = HashMap::from([("author-a".to_string(), user_a(None))]);
+    let groups = vec![
+      ("author-a".to_string(), vec![entry_a_one()]),
+      ("author-c".to_string(), vec![entry_c()]),
+    ];
+
+    assert_eq!(
+      create_avatar_output(&groups, &users, 64),
+      "\n<a href=\"author-a-url\"><img src=\"https://avatars.githubusercontent.com/u/1?v=4&s=64\" width=\"64\" height=\"64\" alt=\"author-a\" title=\"author-a\" /></a>\n".to_string()
+    )
   }
 }